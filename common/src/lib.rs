@@ -0,0 +1,7 @@
+mod sql_bool;
+
+pub use sql_bool::SqlBool;
+
+/// Schema version of the database, the version of the latest indexer
+/// migration. The GraphQL server only serves a database at this version.
+pub const SCHEMA_VERSION: i64 = 20;
@@ -0,0 +1,41 @@
+use sqlx::{
+    Any, Decode, Type, ValueRef,
+    any::{AnyTypeInfo, AnyValueRef},
+    error::{BoxDynError, UnexpectedNullError},
+};
+
+/// Flag column, stored as a BIGINT 0 or 1 on both backends
+pub struct SqlBool(Option<bool>);
+
+impl Type<Any> for SqlBool {
+    fn type_info() -> AnyTypeInfo {
+        <i64 as Type<Any>>::type_info()
+    }
+
+    fn compatible(ty: &AnyTypeInfo) -> bool {
+        <i64 as Type<Any>>::compatible(ty)
+    }
+}
+
+impl<'r> Decode<'r, Any> for SqlBool {
+    fn decode(value: AnyValueRef<'r>) -> Result<Self, BoxDynError> {
+        if value.is_null() {
+            return Ok(SqlBool(None));
+        }
+        Ok(SqlBool(Some(<i64 as Decode<Any>>::decode(value)? != 0)))
+    }
+}
+
+impl From<SqlBool> for Option<bool> {
+    fn from(value: SqlBool) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<SqlBool> for bool {
+    type Error = UnexpectedNullError;
+
+    fn try_from(value: SqlBool) -> Result<Self, Self::Error> {
+        value.0.ok_or(UnexpectedNullError)
+    }
+}
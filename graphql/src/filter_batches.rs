@@ -1,6 +1,6 @@
 use crate::models::Batch;
 use async_graphql::Context;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 
 pub async fn filter_batches(
     ctx: &Context<'_>,
@@ -10,20 +10,25 @@ pub async fn filter_batches(
     start: Option<i64>,
     end: Option<i64>,
 ) -> async_graphql::Result<Vec<Batch>> {
-    let pool = ctx.data::<SqlitePool>()?;
-    let mut query = format!("SELECT * FROM batch WHERE {base_condition}");
+    let pool = ctx.data::<AnyPool>()?;
+    let mut query = format!("SELECT * FROM batch_view WHERE {base_condition}");
+    let mut params = 0;
 
     if proposer.is_some() {
-        query.push_str(" AND proposer = ?");
+        params += 1;
+        query.push_str(&format!(" AND proposer = ${params}"));
     }
     if sender.is_some() {
-        query.push_str(" AND sender = ?");
+        params += 1;
+        query.push_str(&format!(" AND sender = ${params}"));
     }
     if start.is_some() {
-        query.push_str(" AND proposed_at >= ?");
+        params += 1;
+        query.push_str(&format!(" AND proposed_at >= ${params}"));
     }
     if end.is_some() {
-        query.push_str(" AND proposed_at <= ?");
+        params += 1;
+        query.push_str(&format!(" AND proposed_at <= ${params}"));
     }
 
     let mut q = sqlx::query_as::<_, Batch>(&query);
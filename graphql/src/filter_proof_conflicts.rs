@@ -0,0 +1,41 @@
+use crate::models::ProofConflict;
+use async_graphql::Context;
+use sqlx::AnyPool;
+
+pub async fn filter_proof_conflicts(
+    ctx: &Context<'_>,
+    proposer: Option<String>,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> async_graphql::Result<Vec<ProofConflict>> {
+    let pool = ctx.data::<AnyPool>()?;
+    let mut query = "SELECT proof_conflict.* FROM proof_conflict JOIN batch ON batch.batch_id = proof_conflict.batch_id WHERE 1 = 1".to_string();
+    let mut params = 0;
+
+    if proposer.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND batch.proposer = ${params}"));
+    }
+    if start.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND batch.proposed_at >= ${params}"));
+    }
+    if end.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND batch.proposed_at <= ${params}"));
+    }
+    query.push_str(" ORDER BY proof_conflict.l1_block, proof_conflict.log_index");
+
+    let mut q = sqlx::query_as::<_, ProofConflict>(&query);
+    if let Some(p) = proposer {
+        q = q.bind(p);
+    }
+    if let Some(s) = start {
+        q = q.bind(s);
+    }
+    if let Some(e) = end {
+        q = q.bind(e);
+    }
+
+    Ok(q.fetch_all(pool).await?)
+}
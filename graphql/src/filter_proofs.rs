@@ -0,0 +1,49 @@
+use crate::models::BatchProof;
+use async_graphql::Context;
+use sqlx::AnyPool;
+
+pub async fn filter_proofs(
+    ctx: &Context<'_>,
+    proof_type: Option<String>,
+    prover: Option<String>,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> async_graphql::Result<Vec<BatchProof>> {
+    let pool = ctx.data::<AnyPool>()?;
+    let mut query = "SELECT * FROM batch_proof_view WHERE 1 = 1".to_string();
+    let mut params = 0;
+
+    if proof_type.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND COALESCE(proof_type, 'unknown') = ${params}"));
+    }
+    if prover.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND prover = ${params}"));
+    }
+    if start.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND l1_timestamp >= ${params}"));
+    }
+    if end.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND l1_timestamp <= ${params}"));
+    }
+    query.push_str(" ORDER BY l1_block, log_index");
+
+    let mut q = sqlx::query_as::<_, BatchProof>(&query);
+    if let Some(t) = proof_type {
+        q = q.bind(t.to_lowercase());
+    }
+    if let Some(p) = prover {
+        q = q.bind(p);
+    }
+    if let Some(s) = start {
+        q = q.bind(s);
+    }
+    if let Some(e) = end {
+        q = q.bind(e);
+    }
+
+    Ok(q.fetch_all(pool).await?)
+}
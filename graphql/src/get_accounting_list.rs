@@ -1,6 +1,6 @@
 use crate::models::{AccountingList, AccountingOperation, Batch};
 use async_graphql::Context;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 
 pub async fn get_accounting_list(
     ctx: &Context<'_>,
@@ -9,19 +9,19 @@ pub async fn get_accounting_list(
     from: i64,
     to: i64,
 ) -> async_graphql::Result<AccountingList> {
-    let pool = ctx.data::<SqlitePool>()?;
+    let pool = ctx.data::<AnyPool>()?;
 
-    let mut query = "SELECT * FROM batch WHERE".to_string();
+    let mut query = "SELECT * FROM batch_view WHERE".to_string();
     match operation {
         AccountingOperation::Debit => {
-            query.push_str(" proposer = ? AND coinbase <> ?");
+            query.push_str(" proposer = $1 AND coinbase <> $2");
         }
         AccountingOperation::Credit => {
-            query.push_str(" proposer <> ? AND coinbase = ?");
+            query.push_str(" proposer <> $1 AND coinbase = $2");
         }
     }
 
-    query.push_str(" AND batch_id >= ? AND batch_id <= ?");
+    query.push_str(" AND batch_id >= $3 AND batch_id <= $4");
 
     let batches: Vec<Batch> = sqlx::query_as::<_, Batch>(&query)
         .bind(&address)
@@ -0,0 +1,93 @@
+use crate::models::{BondBalance, BondEvent, BondLedger, BondLedgerEntry};
+use alloy::primitives::I256;
+use async_graphql::Context;
+use sqlx::AnyPool;
+
+pub async fn get_bond_ledger(
+    ctx: &Context<'_>,
+    address: String,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> async_graphql::Result<BondLedger> {
+    let pool = ctx.data::<AnyPool>()?;
+
+    // the whole history is needed to compute the running balance
+    let events: Vec<BondEvent> = sqlx::query_as::<_, BondEvent>(
+        "SELECT * FROM bond_event WHERE address = $1 ORDER BY l1_block, log_index",
+    )
+    .bind(&address)
+    .fetch_all(pool)
+    .await?;
+
+    let first_reconciled: Option<BondBalance> = sqlx::query_as::<_, BondBalance>(
+        "SELECT * FROM bond_balance WHERE address = $1 ORDER BY l1_block LIMIT 1",
+    )
+    .bind(&address)
+    .fetch_optional(pool)
+    .await?;
+    let reconciled: Option<BondBalance> = sqlx::query_as::<_, BondBalance>(
+        "SELECT * FROM bond_balance WHERE address = $1 ORDER BY l1_block DESC LIMIT 1",
+    )
+    .bind(&address)
+    .fetch_optional(pool)
+    .await?;
+
+    // balance held before the first indexed event
+    let mut balance = match &first_reconciled {
+        Some(reconciled) => {
+            parse_amount(&reconciled.balance)? - parse_amount(&reconciled.ledger_balance)?
+        }
+        None => I256::ZERO,
+    };
+    let mut opening_balance = None;
+    let mut entries = Vec::new();
+
+    for event in events {
+        if end.is_some_and(|end| event.l1_timestamp > end) {
+            break;
+        }
+
+        let amount = parse_amount(&event.amount)?;
+        let in_range = start.is_none_or(|start| event.l1_timestamp >= start);
+        if in_range && opening_balance.is_none() {
+            opening_balance = Some(balance);
+        }
+
+        balance = match event.kind.as_str() {
+            "deposited" | "credited" => balance + amount,
+            "withdrawn" | "debited" => balance - amount,
+            kind => {
+                return Err(async_graphql::Error::new(format!(
+                    "Unknown bond event kind: {kind}"
+                )));
+            }
+        };
+
+        if in_range {
+            entries.push(BondLedgerEntry {
+                tx_hash: event.tx_hash,
+                log_index: event.log_index,
+                l1_block: event.l1_block,
+                l1_timestamp: event.l1_timestamp,
+                kind: event.kind,
+                amount: event.amount,
+                running_balance: balance.to_string(),
+            });
+        }
+    }
+
+    Ok(BondLedger {
+        address,
+        opening_balance: opening_balance.unwrap_or(balance).to_string(),
+        closing_balance: balance.to_string(),
+        onchain_balance: reconciled.as_ref().map(|r| r.balance.clone()),
+        reconciled_l1_block: reconciled.as_ref().map(|r| r.l1_block),
+        drift: reconciled.map(|r| r.drift),
+        entries,
+    })
+}
+
+fn parse_amount(amount: &str) -> async_graphql::Result<I256> {
+    I256::from_dec_str(amount)
+        .map_err(|e| async_graphql::Error::new(format!("Cannot parse bond amount: {e}")))
+}
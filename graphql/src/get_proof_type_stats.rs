@@ -0,0 +1,88 @@
+use crate::models::ProofTypeStats;
+use alloy::primitives::U256;
+use async_graphql::Context;
+use sqlx::AnyPool;
+use std::collections::BTreeMap;
+
+#[derive(Default)]
+struct ProofTypeTotals {
+    block_count: i64,
+    prove_fee: U256,
+    latencies: Vec<i64>,
+}
+
+pub async fn get_proof_type_stats(
+    ctx: &Context<'_>,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> async_graphql::Result<Vec<ProofTypeStats>> {
+    let pool = ctx.data::<AnyPool>()?;
+    let mut query = r#"
+        SELECT
+            COALESCE(batch_proof_view.proof_type, 'unknown'),
+            batch_proof_view.prove_fee,
+            batch_proof_view.l1_timestamp - batch.propose_l1_timestamp,
+            batch.block_count
+        FROM batch_proof_view
+        JOIN batch ON batch.batch_id = batch_proof_view.batch_id
+        WHERE batch.propose_l1_timestamp IS NOT NULL"#
+        .to_string();
+    let mut params = 0;
+
+    if start.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND batch_proof_view.l1_timestamp >= ${params}"));
+    }
+    if end.is_some() {
+        params += 1;
+        query.push_str(&format!(" AND batch_proof_view.l1_timestamp <= ${params}"));
+    }
+
+    let mut q = sqlx::query_as::<_, (String, String, i64, i64)>(&query);
+    if let Some(s) = start {
+        q = q.bind(s);
+    }
+    if let Some(e) = end {
+        q = q.bind(e);
+    }
+
+    let mut totals: BTreeMap<String, ProofTypeTotals> = BTreeMap::new();
+    for (proof_type, prove_fee, latency, block_count) in q.fetch_all(pool).await? {
+        let prove_fee = U256::from_str_radix(&prove_fee, 10)
+            .map_err(|e| async_graphql::Error::new(format!("Cannot parse prove fee: {e}")))?;
+        let proof_type_totals = totals.entry(proof_type).or_default();
+        proof_type_totals.block_count += block_count;
+        proof_type_totals.prove_fee += prove_fee;
+        proof_type_totals.latencies.push(latency);
+    }
+
+    Ok(totals
+        .into_iter()
+        .map(|(proof_type, mut totals)| {
+            totals.latencies.sort_unstable();
+            let proof_count = i64::try_from(totals.latencies.len()).unwrap_or(i64::MAX);
+            ProofTypeStats {
+                proof_type,
+                proof_count,
+                block_count: totals.block_count,
+                total_prove_fee: totals.prove_fee.to_string(),
+                avg_prove_fee: average(totals.prove_fee, proof_count),
+                avg_prove_fee_per_block: average(totals.prove_fee, totals.block_count),
+                avg_prove_latency: totals.latencies.iter().sum::<i64>() / proof_count.max(1),
+                median_prove_latency: totals
+                    .latencies
+                    .get(totals.latencies.len() / 2)
+                    .copied()
+                    .unwrap_or_default(),
+                max_prove_latency: totals.latencies.last().copied().unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+fn average(total: U256, count: i64) -> String {
+    match u64::try_from(count) {
+        Ok(count) if count > 0 => (total / U256::from(count)).to_string(),
+        _ => "0".to_string(),
+    }
+}
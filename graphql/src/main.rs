@@ -1,5 +1,9 @@
 mod filter_batches;
+mod filter_proof_conflicts;
+mod filter_proofs;
 mod get_accounting_list;
+mod get_bond_ledger;
+mod get_proof_type_stats;
 mod models;
 mod schema;
 
@@ -13,8 +17,12 @@ use axum::{
     response::{Html, IntoResponse},
     routing::get,
 };
+use batch_tracker_common::SCHEMA_VERSION;
 use schema::{AppSchema, QueryRoot};
-use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{
+    AnyPool,
+    any::{AnyPoolOptions, install_default_drivers},
+};
 use tokio::net::TcpListener;
 
 #[tokio::main]
@@ -26,9 +34,17 @@ async fn main() -> anyhow::Result<()> {
     // Load environment variables from .env file
     dotenvy::dotenv().ok();
 
-    let db_filename = std::env::var("DB_FILENAME").unwrap_or_else(|_| {
-        panic!("DB_FILENAME env var not found");
-    });
+    // DATABASE_URL selects the storage backend, DB_FILENAME is a shortcut for
+    // a SQLite database file
+    let database_url = std::env::var("DATABASE_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| {
+            let db_filename = std::env::var("DB_FILENAME").unwrap_or_else(|_| {
+                panic!("DB_FILENAME or DATABASE_URL env var not found");
+            });
+            format!("sqlite://{db_filename}")
+        });
 
     let port_number = std::env::var("PORT")
         .unwrap_or("8000".to_string())
@@ -40,10 +56,12 @@ async fn main() -> anyhow::Result<()> {
         })
         .expect("PORT must be a u16 number");
 
-    let pool = SqlitePoolOptions::new()
+    install_default_drivers();
+    let pool = AnyPoolOptions::new()
         .max_connections(5)
-        .connect(&db_filename)
+        .connect(&database_url)
         .await?;
+    check_schema_version(&pool).await?;
 
     let schema = AppSchema::build(QueryRoot, EmptyMutation, EmptySubscription)
         .data(pool)
@@ -63,6 +81,31 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Refuses to serve a database that is not at SCHEMA_VERSION, either not
+/// migrated yet or migrated by a newer indexer whose schema it may not read
+async fn check_schema_version(pool: &AnyPool) -> anyhow::Result<()> {
+    let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_migration")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!("Database has no schema version, start the indexer first: {e}")
+        })?;
+    let version = version.unwrap_or(0);
+
+    if version < SCHEMA_VERSION {
+        anyhow::bail!(
+            "Database schema version {version} is older than the required version {SCHEMA_VERSION}, start the indexer to migrate it"
+        );
+    }
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "Database schema version {version} is newer than the supported version {SCHEMA_VERSION}, upgrade the GraphQL server"
+        );
+    }
+
+    Ok(())
+}
+
 async fn graphql_handler(State(schema): State<AppSchema>, req: GraphQLRequest) -> GraphQLResponse {
     schema.execute(req.into_inner()).await.into()
 }
@@ -1,6 +1,12 @@
-use async_graphql::SimpleObject;
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use sqlx::AnyPool;
+
+use crate::models::{
+    BatchInfo, BatchProof, L2Block, ProofConflict, ProtocolConfig, SqlBool, TxCost,
+};
 
 #[derive(Debug, sqlx::FromRow, SimpleObject)]
+#[graphql(complex)]
 pub struct Batch {
     pub batch_id: i64,
     /// Batch sender to L1
@@ -13,24 +19,161 @@ pub struct Batch {
     pub propose_tx: String,
     /// Timestamp of block with proposeBatch transaction
     pub proposed_at: i64,
+    /// L1 block with the BatchProposed event
+    pub propose_l1_block: i64,
+    /// Timestamp of the L1 block with the BatchProposed event
+    pub propose_l1_timestamp: Option<i64>,
+    /// Protocol fork whose event proposed the batch: ontake or pacaya.
+    /// Ontake proposals are batches of a single block.
+    pub fork: String,
     /// Last block in the batch
     pub last_block_id: i64,
     /// Number of blocks in the batch
     pub block_count: i64,
-    /// Fee to call proposeBatch on L1
+    /// Share of the proposeBatch transaction fee on L1 charged to the batch
     pub propose_fee: String,
-    /// L2 fee earned for preconfirmation
+    /// Rule used to split the transaction fee between the batches it proposes:
+    /// even, blob_bytes or block_count
+    pub propose_fee_split: Option<String>,
+    /// L2 fee earned for preconfirmation, l2_priority_fee + l2_base_fee_share
     pub l2_fee_earned: Option<String>,
+    /// Priority fees paid in the L2 blocks of the batch
+    pub l2_priority_fee: Option<String>,
+    /// Coinbase share of the base fee paid in the L2 blocks of the batch
+    pub l2_base_fee_share: Option<String>,
+    /// Percentage of the L2 base fee shared with the coinbase
+    pub base_fee_sharing_pctg: Option<i64>,
     /// Address wich receives TAIKO tokens after proving
     pub prover: Option<String>,
-    /// proveBatch transaction hash on L1
+    /// How the prover of the effective proof was determined: transition,
+    /// event or proving_window
+    pub prover_attribution: Option<String>,
+    /// Flag indicating if the effective proof was submitted in the proving
+    /// window, null for Ontake proofs whose window depends on their tier
+    #[sqlx(try_from = "SqlBool")]
+    pub in_proving_window: Option<bool>,
+    /// Proof type of the verifier of the effective proof
+    pub proof_type: Option<String>,
+    /// proveBatch transaction hash on L1 of the effective proof
     pub prove_tx: Option<String>,
-    /// Fee to call proveBatch on L1
+    /// L1 block with the BatchesProved event of the effective proof
+    pub prove_l1_block: Option<i64>,
+    /// Timestamp of the L1 block with the BatchesProved event of the effective proof
+    pub prove_l1_timestamp: Option<i64>,
+    /// Seconds from the proposal to the effective proof on L1
+    pub time_to_prove: Option<i64>,
+    /// Share of the proveBatches transaction fee on L1 of the effective proof
     pub prove_fee: Option<String>,
+    /// Rule used to split the proveBatches transaction fee between its batches,
+    /// even when any of them was not indexed yet at the proof
+    pub prove_fee_split: Option<String>,
     /// Flag indicating if proposeBatch transaction was sent by the proposer
+    #[sqlx(try_from = "SqlBool")]
     pub is_sent_by_proposer: bool,
     /// Flag indecating if l2_fee_earned >= propose_fee + prove_fee
+    #[sqlx(try_from = "SqlBool")]
     pub is_profitable: Option<bool>,
     /// Flag indecating if TAIKO tokens were sent to proposer
+    #[sqlx(try_from = "SqlBool")]
     pub is_proved_by_proposer: Option<bool>,
+    /// L1 block with the BatchesVerified event that verified the batch
+    pub verified_l1_block: Option<i64>,
+    /// Timestamp of the L1 block that verified the batch
+    pub verified_at: Option<i64>,
+    /// Block hash reported by the BatchesVerified event, set on the batch it
+    /// names only as it is the hash of that batch's last block
+    pub verified_block_hash: Option<String>,
+}
+
+#[ComplexObject]
+impl Batch {
+    /// All proofs submitted for the batch
+    async fn proofs(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<BatchProof>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let proofs = sqlx::query_as::<_, BatchProof>(
+            "SELECT * FROM batch_proof_view WHERE batch_id = $1 ORDER BY l1_block, log_index",
+        )
+        .bind(self.batch_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(proofs)
+    }
+
+    /// Cost components of the proposeBatch transaction
+    async fn propose_cost(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<TxCost>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let cost = sqlx::query_as::<_, TxCost>("SELECT * FROM tx_cost WHERE tx_hash = $1")
+            .bind(&self.propose_tx)
+            .fetch_optional(pool)
+            .await?;
+        Ok(cost)
+    }
+
+    /// Cost components of the proveBatches transaction of the effective proof
+    async fn prove_cost(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<TxCost>> {
+        let Some(prove_tx) = &self.prove_tx else {
+            return Ok(None);
+        };
+        let pool = ctx.data::<AnyPool>()?;
+        let cost = sqlx::query_as::<_, TxCost>("SELECT * FROM tx_cost WHERE tx_hash = $1")
+            .bind(prove_tx)
+            .fetch_optional(pool)
+            .await?;
+        Ok(cost)
+    }
+
+    /// Info and meta fields of the BatchProposed event
+    async fn info(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<BatchInfo>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let info = sqlx::query_as::<_, BatchInfo>("SELECT * FROM batch_info WHERE batch_id = $1")
+            .bind(self.batch_id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(info)
+    }
+
+    /// Protocol config in force when the batch was proposed, which its proof is
+    /// evaluated against
+    async fn protocol_config(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Option<ProtocolConfig>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let config = sqlx::query_as::<_, ProtocolConfig>(
+            r#"
+            SELECT * FROM protocol_config
+            WHERE l1_block <= $1 OR l1_block = (SELECT MIN(l1_block) FROM protocol_config)
+            ORDER BY l1_block DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(self.propose_l1_block)
+        .fetch_optional(pool)
+        .await?;
+        Ok(config)
+    }
+
+    /// L2 blocks of the batch
+    async fn blocks(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<L2Block>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let blocks = sqlx::query_as::<_, L2Block>(
+            "SELECT * FROM l2_block WHERE batch_id = $1 ORDER BY block_number",
+        )
+        .bind(self.batch_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(blocks)
+    }
+
+    /// Conflicting proofs submitted for the batch
+    async fn conflicts(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ProofConflict>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let conflicts = sqlx::query_as::<_, ProofConflict>(
+            "SELECT * FROM proof_conflict WHERE batch_id = $1 ORDER BY l1_block, log_index",
+        )
+        .bind(self.batch_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(conflicts)
+    }
 }
@@ -0,0 +1,84 @@
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use sqlx::AnyPool;
+
+use crate::models::Batch;
+
+/// BatchProposed info and meta fields of a batch
+#[derive(Debug, sqlx::FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct BatchInfo {
+    pub batch_id: i64,
+    /// Hash of the BatchInfo, from the batch meta
+    pub info_hash: String,
+    /// Hash of the transaction list
+    pub txs_hash: String,
+    /// Extra data of the L2 blocks
+    pub extra_data: String,
+    /// L1 block the batch was proposed in
+    pub proposed_in: i64,
+    /// L1 block the blobs were created in, earlier than proposed_in for reused blobs
+    pub blob_created_in: i64,
+    /// Offset of the batch data in the blobs
+    pub blob_byte_offset: i64,
+    /// Size of the batch data in the blobs
+    pub blob_byte_size: i64,
+    /// Gas limit of the L2 blocks
+    pub gas_limit: i64,
+    /// Timestamp of the last L2 block
+    pub last_block_timestamp: i64,
+    /// L1 block the L2 blocks are anchored to
+    pub anchor_block_id: i64,
+    /// Hash of the anchor L1 block
+    pub anchor_block_hash: String,
+    /// Base fee config adjustment quotient
+    pub adjustment_quotient: i64,
+    /// Base fee config percentage of the base fee shared with the coinbase
+    pub sharing_pctg: i64,
+    /// Base fee config gas issuance per second
+    pub gas_issuance_per_second: i64,
+    /// Base fee config minimal gas excess
+    pub min_gas_excess: String,
+    /// Base fee config maximal gas issuance per block
+    pub max_gas_issuance_per_block: i64,
+}
+
+#[ComplexObject]
+impl BatchInfo {
+    /// L1 blocks between the anchor block and the proposal
+    async fn anchor_lag(&self) -> i64 {
+        self.proposed_in - self.anchor_block_id
+    }
+
+    /// Blob hashes of the batch in order
+    async fn blob_hashes(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<String>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let blob_hashes = sqlx::query_scalar::<_, String>(
+            "SELECT blob_hash FROM batch_blob WHERE batch_id = $1 ORDER BY blob_index",
+        )
+        .bind(self.batch_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(blob_hashes)
+    }
+
+    /// Other batches that use any of the blobs of the batch
+    async fn blob_reused_by(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Batch>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let batches = sqlx::query_as::<_, Batch>(
+            r#"
+            SELECT * FROM batch_view
+            WHERE batch_id <> $1 AND batch_id IN (
+                SELECT batch_id FROM batch_blob WHERE blob_hash IN (
+                    SELECT blob_hash FROM batch_blob WHERE batch_id = $2
+                )
+            )
+            ORDER BY batch_id
+            "#,
+        )
+        .bind(self.batch_id)
+        .bind(self.batch_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(batches)
+    }
+}
@@ -0,0 +1,64 @@
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use sqlx::AnyPool;
+
+use crate::models::{SqlBool, TxCost};
+
+#[derive(Debug, sqlx::FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct BatchProof {
+    pub batch_id: i64,
+    /// proveBatches transaction hash on L1
+    pub prove_tx: String,
+    /// L1 block with the proveBatches transaction
+    pub l1_block: i64,
+    /// Timestamp of the L1 block with the proveBatches transaction
+    pub l1_timestamp: i64,
+    /// Log index of the BatchesProved event
+    pub log_index: i64,
+    /// Verifier address from the BatchesProved event, or the proof tier of
+    /// Ontake proofs, e.g. tier:200
+    pub verifier: String,
+    /// Proof type of the verifier from the verifier registry
+    pub proof_type: Option<String>,
+    /// Transition parent hash
+    pub parent_hash: String,
+    /// Transition block hash
+    pub block_hash: String,
+    /// Transition state root
+    pub state_root: String,
+    /// Share of the proveBatches fee charged to the batch
+    pub prove_fee: String,
+    /// Rule used to split the transaction fee between the batches it proves:
+    /// even, block_count or l2_gas. Even when any of those batches was not
+    /// indexed yet at the proof, the split is not recomputed once they are.
+    pub prove_fee_split: Option<String>,
+    /// Address wich receives TAIKO tokens after proving
+    pub prover: String,
+    /// Flag indicating if the proof was submitted in the proving window, null
+    /// for Ontake proofs whose window depends on their tier
+    #[sqlx(try_from = "SqlBool")]
+    pub in_proving_window: Option<bool>,
+    /// How the prover was determined: transition when read from the
+    /// transition stored by the inbox, event when named by the Ontake proof
+    /// event, proving_window when guessed from the proof time
+    pub prover_attribution: Option<String>,
+    /// Flag indecating if TAIKO tokens were sent to proposer
+    #[sqlx(try_from = "SqlBool")]
+    pub is_proved_by_proposer: bool,
+    /// Flag indecating if l2_fee_earned >= propose_fee + prove_fee
+    #[sqlx(try_from = "SqlBool")]
+    pub is_profitable: bool,
+}
+
+#[ComplexObject]
+impl BatchProof {
+    /// Cost components of the proveBatches transaction
+    async fn prove_cost(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<TxCost>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let cost = sqlx::query_as::<_, TxCost>("SELECT * FROM tx_cost WHERE tx_hash = $1")
+            .bind(&self.prove_tx)
+            .fetch_optional(pool)
+            .await?;
+        Ok(cost)
+    }
+}
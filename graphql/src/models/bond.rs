@@ -0,0 +1,78 @@
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use sqlx::AnyPool;
+
+use crate::models::Batch;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct BondEvent {
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub l1_block: i64,
+    pub l1_timestamp: i64,
+    pub kind: String,
+    pub amount: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct BondBalance {
+    pub l1_block: i64,
+    pub balance: String,
+    pub ledger_balance: String,
+    pub drift: String,
+}
+
+#[derive(Debug, SimpleObject)]
+#[graphql(complex)]
+pub struct BondLedgerEntry {
+    /// L1 transaction hash with the bond event
+    pub tx_hash: String,
+    /// Log index of the bond event
+    pub log_index: i64,
+    /// L1 block with the bond event
+    pub l1_block: i64,
+    /// Timestamp of the L1 block with the bond event
+    pub l1_timestamp: i64,
+    /// One of deposited, withdrawn, credited or debited
+    pub kind: String,
+    /// Bond amount in wei
+    pub amount: String,
+    /// Bond balance in wei after the event
+    pub running_balance: String,
+}
+
+#[ComplexObject]
+impl BondLedgerEntry {
+    /// Batches proposed or proved in the transaction with the bond event
+    async fn batches(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Batch>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let batches = sqlx::query_as::<_, Batch>(
+            r#"
+            SELECT * FROM batch_view
+            WHERE propose_tx = $1
+                OR batch_id IN (SELECT batch_id FROM batch_proof WHERE prove_tx = $2)
+            "#,
+        )
+        .bind(&self.tx_hash)
+        .bind(&self.tx_hash)
+        .fetch_all(pool)
+        .await?;
+        Ok(batches)
+    }
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct BondLedger {
+    pub address: String,
+    /// Bond balance in wei before the first entry
+    pub opening_balance: String,
+    /// Bond balance in wei after the last entry
+    pub closing_balance: String,
+    /// bondBalanceOf at the last reconciled L1 block
+    pub onchain_balance: Option<String>,
+    /// L1 block of the last reconciliation with bondBalanceOf
+    pub reconciled_l1_block: Option<i64>,
+    /// Amount in wei by which bondBalanceOf at the last reconciliation differs
+    /// from the ledger, non-zero when bond events are missing
+    pub drift: Option<String>,
+    pub entries: Vec<BondLedgerEntry>,
+}
@@ -0,0 +1,37 @@
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use sqlx::AnyPool;
+
+use crate::models::Batch;
+
+#[derive(Debug, sqlx::FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct L2Block {
+    /// L2 block number
+    pub block_number: i64,
+    /// Batch that contains the block
+    pub batch_id: i64,
+    /// Block timestamp reconstructed from the batch lastBlockTimestamp and time shifts
+    pub timestamp: i64,
+    /// Number of transactions in the block params of the batch, not known for
+    /// Ontake blocks
+    pub tx_count: Option<i64>,
+    /// Number of signal slots in the block params of the batch
+    pub signal_count: i64,
+    /// Gas used by the block, read from L2 when the batch is proposed
+    pub gas_used: Option<i64>,
+    /// Base fee per gas of the block, read from L2 when the batch is proposed
+    pub base_fee: Option<String>,
+}
+
+#[ComplexObject]
+impl L2Block {
+    /// Batch that contains the block
+    async fn batch(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Batch>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let batch = sqlx::query_as::<_, Batch>("SELECT * FROM batch_view WHERE batch_id = $1")
+            .bind(self.batch_id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(batch)
+    }
+}
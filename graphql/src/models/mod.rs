@@ -1,6 +1,23 @@
 mod accounting;
 mod batch;
+mod batch_info;
+mod batch_proof;
+mod bond;
+mod l2_block;
+mod proof_conflict;
+mod proof_type_stats;
+mod protocol_config;
 mod status;
+mod tx_cost;
 pub use accounting::{AccountingList, AccountingListGql, AccountingOperation, AccountingResult};
 pub use batch::Batch;
+pub use batch_info::BatchInfo;
+pub use batch_proof::BatchProof;
+pub use batch_tracker_common::SqlBool;
+pub use bond::{BondBalance, BondEvent, BondLedger, BondLedgerEntry};
+pub use l2_block::L2Block;
+pub use proof_conflict::ProofConflict;
+pub use proof_type_stats::ProofTypeStats;
+pub use protocol_config::ProtocolConfig;
 pub use status::Status;
+pub use tx_cost::TxCost;
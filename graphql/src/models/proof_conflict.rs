@@ -0,0 +1,34 @@
+use async_graphql::SimpleObject;
+
+use crate::models::SqlBool;
+
+#[derive(Debug, sqlx::FromRow, SimpleObject)]
+pub struct ProofConflict {
+    /// Contested batch
+    pub batch_id: i64,
+    /// L1 block with the ConflictingProof event
+    pub l1_block: i64,
+    /// proveBatches transaction hash on L1 that caused the conflict
+    pub tx_hash: String,
+    /// Log index of the ConflictingProof event
+    pub log_index: i64,
+    /// Parent hash of the previously stored transition
+    pub old_parent_hash: String,
+    /// Block hash of the previously stored transition
+    pub old_block_hash: String,
+    /// State root of the previously stored transition
+    pub old_state_root: String,
+    /// Prover of the previously stored transition
+    pub old_prover: String,
+    /// Flag indicating if the previous transition was proven inside the proving window
+    #[sqlx(try_from = "SqlBool")]
+    pub old_in_proving_window: bool,
+    /// Timestamp when the previous transition was created
+    pub old_created_at: i64,
+    /// Parent hash of the conflicting transition
+    pub new_parent_hash: String,
+    /// Block hash of the conflicting transition
+    pub new_block_hash: String,
+    /// State root of the conflicting transition
+    pub new_state_root: String,
+}
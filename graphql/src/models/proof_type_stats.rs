@@ -0,0 +1,27 @@
+use async_graphql::SimpleObject;
+
+/// Cost and latency of the proofs of one proof type
+#[derive(Debug, SimpleObject)]
+pub struct ProofTypeStats {
+    /// Proof type of the verifier, unknown for verifiers missing from the registry
+    pub proof_type: String,
+    /// Number of proofs submitted
+    pub proof_count: i64,
+    /// Number of L2 blocks in the proved batches
+    pub block_count: i64,
+    /// Sum of the prove fees charged to the proved batches, in wei
+    pub total_prove_fee: String,
+    /// Average prove fee per proof, in wei
+    pub avg_prove_fee: String,
+    /// Average prove fee per proved L2 block, in wei
+    pub avg_prove_fee_per_block: String,
+    /// Average seconds from the L1 block of the proposal to the L1 block of
+    /// the proof
+    pub avg_prove_latency: i64,
+    /// Median seconds from the L1 block of the proposal to the L1 block of
+    /// the proof
+    pub median_prove_latency: i64,
+    /// Longest seconds from the L1 block of the proposal to the L1 block of
+    /// the proof
+    pub max_prove_latency: i64,
+}
@@ -0,0 +1,27 @@
+use async_graphql::SimpleObject;
+
+/// Inbox configuration read from pacayaConfig, in force from `l1Block` until
+/// the next recorded config
+#[derive(Debug, sqlx::FromRow, SimpleObject)]
+pub struct ProtocolConfig {
+    /// L1 block from which the config applies
+    pub l1_block: i64,
+    /// Seconds after the proposal in which only the proposer can prove a batch
+    pub proving_window: i64,
+    /// Seconds after a proof before the batch can be verified
+    pub cooldown_window: i64,
+    /// Liveness bond charged per batch, in wei
+    pub liveness_bond_base: String,
+    /// Liveness bond charged per L2 block of a batch, in wei
+    pub liveness_bond_per_block: String,
+    /// Maximal number of L2 blocks in a batch
+    pub max_blocks_per_batch: i64,
+    /// L2 block of the Ontake fork
+    pub ontake_fork_height: i64,
+    /// L2 block of the Pacaya fork
+    pub pacaya_fork_height: i64,
+    /// L2 block of the Shasta fork
+    pub shasta_fork_height: i64,
+    /// L2 block of the Unzen fork
+    pub unzen_fork_height: i64,
+}
@@ -1,6 +1,8 @@
-use async_graphql::SimpleObject;
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use sqlx::AnyPool;
 
 #[derive(Debug, sqlx::FromRow, SimpleObject)]
+#[graphql(complex)]
 pub struct Status {
     pub id: i64,
     /// Last indexed L1 block
@@ -13,4 +15,18 @@ pub struct Status {
     pub proved_batch_id: i64,
     /// Last indexed proven block
     pub proved_block_id: i64,
+    /// Last indexed verified batch
+    pub verified_batch_id: i64,
+}
+
+#[ComplexObject]
+impl Status {
+    /// Number of proofs waiting for their batches to be indexed
+    async fn pending_proofs(&self, ctx: &Context<'_>) -> async_graphql::Result<i64> {
+        let pool = ctx.data::<AnyPool>()?;
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM pending_proof")
+            .fetch_one(pool)
+            .await?;
+        Ok(count)
+    }
 }
@@ -0,0 +1,39 @@
+use async_graphql::{ComplexObject, SimpleObject};
+
+#[derive(Debug, sqlx::FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct TxCost {
+    /// Transaction hash on L1
+    pub tx_hash: String,
+    /// L1 block with the transaction
+    pub l1_block: i64,
+    /// Execution gas used by the transaction
+    pub gas_used: i64,
+    /// Price paid per execution gas, base fee plus priority fee
+    pub effective_gas_price: String,
+    /// Base fee per gas of the L1 block
+    pub base_fee_per_gas: String,
+    /// Priority fee per gas paid to the L1 block builder
+    pub priority_fee_per_gas: String,
+    /// Blob gas used by the transaction
+    pub blob_gas_used: i64,
+    /// Price paid per blob gas
+    pub blob_gas_price: String,
+    /// Number of blobs carried by the transaction
+    pub blob_count: i64,
+}
+
+#[ComplexObject]
+impl TxCost {
+    /// Execution fee of the transaction, gas_used * effective_gas_price
+    async fn execution_fee(&self) -> async_graphql::Result<String> {
+        let gas_price = self.effective_gas_price.parse::<u128>()?;
+        Ok((u128::try_from(self.gas_used)? * gas_price).to_string())
+    }
+
+    /// Blob fee of the transaction, blob_gas_used * blob_gas_price
+    async fn blob_fee(&self) -> async_graphql::Result<String> {
+        let blob_gas_price = self.blob_gas_price.parse::<u128>()?;
+        Ok((u128::try_from(self.blob_gas_used)? * blob_gas_price).to_string())
+    }
+}
@@ -1,8 +1,15 @@
 use crate::filter_batches::filter_batches;
+use crate::filter_proof_conflicts::filter_proof_conflicts;
+use crate::filter_proofs::filter_proofs;
 use crate::get_accounting_list::get_accounting_list;
-use crate::models::{AccountingListGql, AccountingOperation, AccountingResult, Batch, Status};
+use crate::get_bond_ledger::get_bond_ledger;
+use crate::get_proof_type_stats::get_proof_type_stats;
+use crate::models::{
+    AccountingListGql, AccountingOperation, AccountingResult, Batch, BatchProof, BondLedger,
+    L2Block, ProofConflict, ProofTypeStats, ProtocolConfig, Status,
+};
 use async_graphql::{Context, Object, Schema};
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 
 pub struct QueryRoot;
 
@@ -10,7 +17,7 @@ pub struct QueryRoot;
 impl QueryRoot {
     /// Returns the status of the server
     async fn status(&self, ctx: &Context<'_>) -> async_graphql::Result<Status> {
-        let pool = ctx.data::<SqlitePool>()?;
+        let pool = ctx.data::<AnyPool>()?;
         let status = sqlx::query_as::<_, Status>("SELECT * FROM status WHERE id = 0")
             .fetch_one(pool)
             .await?;
@@ -24,9 +31,9 @@ impl QueryRoot {
         ctx: &Context<'_>,
         timestamp: i64,
     ) -> async_graphql::Result<Option<i64>> {
-        let pool = ctx.data::<SqlitePool>()?;
+        let pool = ctx.data::<AnyPool>()?;
         let batch_id =
-            sqlx::query_scalar::<_, i64>("SELECT MAX(batch_id) FROM batch WHERE proposed_at <= ?")
+            sqlx::query_scalar::<_, i64>("SELECT MAX(batch_id) FROM batch WHERE proposed_at <= $1")
                 .bind(timestamp)
                 .fetch_optional(pool)
                 .await?;
@@ -54,7 +61,7 @@ impl QueryRoot {
         to: i64,
         check_integrity: Option<bool>,
     ) -> async_graphql::Result<AccountingResult> {
-        let pool = ctx.data::<SqlitePool>()?;
+        let pool = ctx.data::<AnyPool>()?;
 
         if from >= to {
             return Err(async_graphql::Error::new("from must be less than to"));
@@ -65,7 +72,7 @@ impl QueryRoot {
         {
             // Count batches in the given range
             let batch_count: Option<i64> = sqlx::query_scalar(
-                "SELECT COUNT(batch_id) FROM batch WHERE batch_id >= ? AND batch_id <= ?",
+                "SELECT COUNT(batch_id) FROM batch WHERE batch_id >= $1 AND batch_id <= $2",
             )
             .bind(from)
             .bind(to)
@@ -106,14 +113,46 @@ impl QueryRoot {
         ctx: &Context<'_>,
         id: i64,
     ) -> async_graphql::Result<Option<Batch>> {
-        let pool = ctx.data::<SqlitePool>()?;
-        let batch = sqlx::query_as::<_, Batch>("SELECT * FROM batch WHERE batch_id = ?")
+        let pool = ctx.data::<AnyPool>()?;
+        let batch = sqlx::query_as::<_, Batch>("SELECT * FROM batch_view WHERE batch_id = $1")
             .bind(id)
             .fetch_optional(pool)
             .await?;
         Ok(batch)
     }
 
+    /// Returns the L2 block with the given number, with the batch that contains it\
+    /// `number`: L2 block number
+    async fn block_by_number(
+        &self,
+        ctx: &Context<'_>,
+        number: i64,
+    ) -> async_graphql::Result<Option<L2Block>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let block = sqlx::query_as::<_, L2Block>("SELECT * FROM l2_block WHERE block_number = $1")
+            .bind(number)
+            .fetch_optional(pool)
+            .await?;
+        Ok(block)
+    }
+
+    /// Returns the batches that use the given blob\
+    /// `blobHash`: Versioned blob hash
+    async fn batches_by_blob_hash(
+        &self,
+        ctx: &Context<'_>,
+        blob_hash: String,
+    ) -> async_graphql::Result<Vec<Batch>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let batches = sqlx::query_as::<_, Batch>(
+            "SELECT * FROM batch_view WHERE batch_id IN (SELECT batch_id FROM batch_blob WHERE blob_hash = $1) ORDER BY batch_id",
+        )
+        .bind(blob_hash.to_lowercase())
+        .fetch_all(pool)
+        .await?;
+        Ok(batches)
+    }
+
     /// Returns batches that were landed on L1 by a different party than the proposer\
     /// `proposer`: Filter by batch proposer address\
     /// `start`: Filter by proposed_at time greater than or equal to this value\
@@ -156,6 +195,75 @@ impl QueryRoot {
     ) -> async_graphql::Result<Vec<Batch>> {
         filter_batches(ctx, "is_profitable = 0", proposer, None, start, end).await
     }
+
+    /// Returns conflicting proofs submitted for batches\
+    /// `proposer`: Filter by batch proposer address\
+    /// `start`: Filter by proposed_at time greater than or equal to this value\
+    /// `end`: Filter by proposed_at time less than or equal to this value\
+    async fn conflicting_proofs(
+        &self,
+        ctx: &Context<'_>,
+        proposer: Option<String>,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> async_graphql::Result<Vec<ProofConflict>> {
+        filter_proof_conflicts(ctx, proposer, start, end).await
+    }
+
+    /// Returns proofs submitted in the given time range\
+    /// `proofType`: Filter by proof type of the verifier, unknown for verifiers missing from the registry\
+    /// `prover`: Filter by prover address\
+    /// `start`: Filter by L1 block timestamp of the proof greater than or equal to this value\
+    /// `end`: Filter by L1 block timestamp of the proof less than or equal to this value\
+    async fn proofs(
+        &self,
+        ctx: &Context<'_>,
+        proof_type: Option<String>,
+        prover: Option<String>,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> async_graphql::Result<Vec<BatchProof>> {
+        filter_proofs(ctx, proof_type, prover, start, end).await
+    }
+
+    /// Returns prove cost and latency per proof type\
+    /// `start`: Filter by L1 block timestamp of the proof greater than or equal to this value\
+    /// `end`: Filter by L1 block timestamp of the proof less than or equal to this value\
+    async fn proof_type_stats(
+        &self,
+        ctx: &Context<'_>,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> async_graphql::Result<Vec<ProofTypeStats>> {
+        get_proof_type_stats(ctx, start, end).await
+    }
+
+    /// Returns every recorded protocol config, oldest first
+    async fn protocol_configs(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<ProtocolConfig>> {
+        let pool = ctx.data::<AnyPool>()?;
+        let configs =
+            sqlx::query_as::<_, ProtocolConfig>("SELECT * FROM protocol_config ORDER BY l1_block")
+                .fetch_all(pool)
+                .await?;
+        Ok(configs)
+    }
+
+    /// Returns bond events of the address with the running bond balance\
+    /// `address`: Bond owner address\
+    /// `start`: Filter by L1 block timestamp greater than or equal to this value\
+    /// `end`: Filter by L1 block timestamp less than or equal to this value\
+    async fn bond_ledger(
+        &self,
+        ctx: &Context<'_>,
+        address: String,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> async_graphql::Result<BondLedger> {
+        get_bond_ledger(ctx, address, start, end).await
+    }
 }
 
 pub type AppSchema =
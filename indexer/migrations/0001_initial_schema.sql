@@ -0,0 +1,38 @@
+-- Schema created by releases before migrations, unchanged so that their
+-- databases are upgraded by the migrations that follow
+CREATE TABLE IF NOT EXISTS batch (
+    batch_id              INTEGER PRIMARY KEY,
+    sender                TEXT NOT NULL,
+    proposer              TEXT NOT NULL,
+    coinbase              TEXT NOT NULL,
+    propose_tx            TEXT NOT NULL,
+    proposed_at           INTEGER NOT NULL,
+    last_block_id         INTEGER NOT NULL,
+    block_count           INTEGER NOT NULL,
+    propose_fee           TEXT NOT NULL,
+    l2_fee_earned         TEXT,
+    prover                TEXT,
+    prove_tx              TEXT,
+    prove_fee             TEXT,
+    is_sent_by_proposer   BOOLEAN NOT NULL,
+    is_profitable         BOOLEAN,
+    is_proved_by_proposer BOOLEAN
+);
+CREATE INDEX IF NOT EXISTS idx_batch_proposed_at ON batch(proposed_at);
+CREATE INDEX IF NOT EXISTS idx_batch_proposer ON batch(proposer);
+CREATE INDEX IF NOT EXISTS idx_batch_profitable ON batch(is_profitable);
+CREATE INDEX IF NOT EXISTS idx_batch_sender ON batch(is_sent_by_proposer);
+CREATE INDEX IF NOT EXISTS idx_batch_proving_window ON batch(is_proved_by_proposer);
+
+-- Indexing status (only one row allowed)
+CREATE TABLE IF NOT EXISTS status (
+    id                  INTEGER PRIMARY KEY CHECK (id = 0),
+    indexed_l1_block    INTEGER NOT NULL,
+    proposed_batch_id   INTEGER NOT NULL,
+    proposed_block_id   INTEGER NOT NULL,
+    proved_batch_id     INTEGER NOT NULL,
+    proved_block_id     INTEGER NOT NULL
+);
+INSERT INTO status (id, indexed_l1_block, proposed_batch_id, proposed_block_id, proved_batch_id, proved_block_id)
+SELECT 0, 0, 0, 0, 0, 0
+WHERE NOT EXISTS (SELECT 1 FROM status WHERE id = 0);
@@ -0,0 +1,10 @@
+-- L1 block with the BatchProposed event, filled by the backfill for batches
+-- indexed before
+ALTER TABLE batch ADD COLUMN propose_l1_block BIGINT NOT NULL DEFAULT 0;
+CREATE INDEX idx_batch_propose_l1_block ON batch(propose_l1_block);
+
+-- Hashes of L1 blocks used for reorg detection
+CREATE TABLE l1_block (
+    block_number  BIGINT PRIMARY KEY,
+    block_hash    TEXT NOT NULL
+);
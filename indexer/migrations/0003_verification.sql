@@ -0,0 +1,8 @@
+-- BatchesVerified event that verified the batch, filled by the backfill for
+-- batches indexed before
+ALTER TABLE batch ADD COLUMN verified_l1_block BIGINT;
+ALTER TABLE batch ADD COLUMN verified_at BIGINT;
+ALTER TABLE batch ADD COLUMN verified_block_hash TEXT;
+CREATE INDEX idx_batch_verified_l1_block ON batch(verified_l1_block);
+
+ALTER TABLE status ADD COLUMN verified_batch_id BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,19 @@
+-- Transitions from ConflictingProof events
+CREATE TABLE proof_conflict (
+    batch_id              BIGINT NOT NULL,
+    l1_block              BIGINT NOT NULL,
+    tx_hash               TEXT NOT NULL,
+    log_index             BIGINT NOT NULL,
+    old_parent_hash       TEXT NOT NULL,
+    old_block_hash        TEXT NOT NULL,
+    old_state_root        TEXT NOT NULL,
+    old_prover            TEXT NOT NULL,
+    old_in_proving_window BIGINT NOT NULL,
+    old_created_at        BIGINT NOT NULL,
+    new_parent_hash       TEXT NOT NULL,
+    new_block_hash        TEXT NOT NULL,
+    new_state_root        TEXT NOT NULL,
+    PRIMARY KEY (tx_hash, log_index)
+);
+CREATE INDEX idx_proof_conflict_batch_id ON proof_conflict(batch_id);
+CREATE INDEX idx_proof_conflict_l1_block ON proof_conflict(l1_block);
@@ -0,0 +1,26 @@
+-- BondDeposited/Withdrawn/Credited/Debited events. Balances held before the
+-- first indexed event show up as the reconciliation offset.
+CREATE TABLE bond_event (
+    tx_hash       TEXT NOT NULL,
+    log_index     BIGINT NOT NULL,
+    l1_block      BIGINT NOT NULL,
+    l1_timestamp  BIGINT NOT NULL,
+    address       TEXT NOT NULL,
+    kind          TEXT NOT NULL,
+    amount        TEXT NOT NULL,
+    PRIMARY KEY (tx_hash, log_index)
+);
+CREATE INDEX idx_bond_event_address ON bond_event(address, l1_block, log_index);
+CREATE INDEX idx_bond_event_l1_block ON bond_event(l1_block);
+
+-- On-chain bond balances used to reconcile the ledger, one row per address and
+-- reconciled L1 block so that a rollback keeps the earlier ones. drift is how
+-- far the ledger moved away from the offset of the first reconciliation.
+CREATE TABLE bond_balance (
+    address         TEXT NOT NULL,
+    l1_block        BIGINT NOT NULL,
+    balance         TEXT NOT NULL,
+    ledger_balance  TEXT NOT NULL,
+    drift           TEXT NOT NULL,
+    PRIMARY KEY (address, l1_block)
+);
@@ -0,0 +1,31 @@
+-- Every proof submitted for a batch, filled by the backfill from the proof
+-- transaction of batches indexed before
+CREATE TABLE batch_proof (
+    batch_id              BIGINT NOT NULL,
+    prove_tx              TEXT NOT NULL,
+    l1_block              BIGINT NOT NULL,
+    l1_timestamp          BIGINT NOT NULL,
+    log_index             BIGINT NOT NULL,
+    verifier              TEXT NOT NULL,
+    parent_hash           TEXT NOT NULL,
+    block_hash            TEXT NOT NULL,
+    state_root            TEXT NOT NULL,
+    prove_fee             TEXT NOT NULL,
+    prove_sender          TEXT NOT NULL,
+    prover                TEXT NOT NULL,
+    is_proved_by_proposer BIGINT NOT NULL,
+    is_profitable         BIGINT NOT NULL,
+    PRIMARY KEY (batch_id, prove_tx)
+);
+CREATE INDEX idx_batch_proof_l1_block ON batch_proof(l1_block);
+CREATE INDEX idx_batch_proof_prove_tx ON batch_proof(prove_tx);
+CREATE INDEX idx_batch_proof_profitable ON batch_proof(is_profitable);
+CREATE INDEX idx_batch_proof_proving_window ON batch_proof(is_proved_by_proposer);
+
+-- The effective proof moves to batch_proof, prove_tx keeps pointing to it
+DROP INDEX idx_batch_profitable;
+DROP INDEX idx_batch_proving_window;
+ALTER TABLE batch DROP COLUMN prover;
+ALTER TABLE batch DROP COLUMN prove_fee;
+ALTER TABLE batch DROP COLUMN is_profitable;
+ALTER TABLE batch DROP COLUMN is_proved_by_proposer;
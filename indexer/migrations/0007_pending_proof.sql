@@ -0,0 +1,16 @@
+-- Proofs of batches that are not indexed yet
+CREATE TABLE pending_proof (
+    batch_id      BIGINT NOT NULL,
+    prove_tx      TEXT NOT NULL,
+    l1_block      BIGINT NOT NULL,
+    l1_timestamp  BIGINT NOT NULL,
+    log_index     BIGINT NOT NULL,
+    verifier      TEXT NOT NULL,
+    parent_hash   TEXT NOT NULL,
+    block_hash    TEXT NOT NULL,
+    state_root    TEXT NOT NULL,
+    prove_fee     TEXT NOT NULL,
+    prove_sender  TEXT NOT NULL,
+    PRIMARY KEY (batch_id, prove_tx)
+);
+CREATE INDEX idx_pending_proof_l1_block ON pending_proof(l1_block);
@@ -0,0 +1,5 @@
+-- L2 revenue split into priority fees and the coinbase share of the base fee,
+-- computed from the L2 blocks of the batch
+ALTER TABLE batch ADD COLUMN base_fee_sharing_pctg BIGINT;
+ALTER TABLE batch ADD COLUMN l2_priority_fee TEXT;
+ALTER TABLE batch ADD COLUMN l2_base_fee_share TEXT;
@@ -0,0 +1,11 @@
+-- L2 blocks of every batch, from the block params of BatchProposed events
+CREATE TABLE l2_block (
+    block_number  BIGINT PRIMARY KEY,
+    batch_id      BIGINT NOT NULL,
+    timestamp     BIGINT NOT NULL,
+    tx_count      BIGINT NOT NULL,
+    signal_count  BIGINT NOT NULL,
+    gas_used      BIGINT,
+    base_fee      TEXT
+);
+CREATE INDEX idx_l2_block_batch_id ON l2_block(batch_id);
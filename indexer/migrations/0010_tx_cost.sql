@@ -0,0 +1,13 @@
+-- Cost components of proposeBatch and proveBatches transactions
+CREATE TABLE tx_cost (
+    tx_hash               TEXT PRIMARY KEY,
+    l1_block              BIGINT NOT NULL,
+    gas_used              BIGINT NOT NULL,
+    effective_gas_price   TEXT NOT NULL,
+    base_fee_per_gas      TEXT NOT NULL,
+    priority_fee_per_gas  TEXT NOT NULL,
+    blob_gas_used         BIGINT NOT NULL,
+    blob_gas_price        TEXT NOT NULL,
+    blob_count            BIGINT NOT NULL
+);
+CREATE INDEX idx_tx_cost_l1_block ON tx_cost(l1_block);
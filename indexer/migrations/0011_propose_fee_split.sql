@@ -0,0 +1,2 @@
+-- Rule used to split the fee of a proposal transaction between its batches
+ALTER TABLE batch ADD COLUMN propose_fee_split TEXT;
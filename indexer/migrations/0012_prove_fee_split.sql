@@ -0,0 +1,3 @@
+-- Rule used to split the fee of a proof transaction between its batches
+ALTER TABLE batch_proof ADD COLUMN prove_fee_split TEXT;
+ALTER TABLE pending_proof ADD COLUMN prove_fee_split TEXT;
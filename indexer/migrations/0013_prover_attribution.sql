@@ -0,0 +1,4 @@
+-- Prover attribution read from the transition stored by the inbox, with the
+-- proving window heuristic as fallback
+ALTER TABLE batch_proof ADD COLUMN in_proving_window BIGINT;
+ALTER TABLE batch_proof ADD COLUMN prover_attribution TEXT;
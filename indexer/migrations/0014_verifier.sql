@@ -0,0 +1,5 @@
+-- Proof type of each verifier address, replaced from VERIFIER_REGISTRY on startup
+CREATE TABLE verifier (
+    address     TEXT PRIMARY KEY,
+    proof_type  TEXT NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Timestamp of the L1 block with the BatchProposed event, and the proof L1
+-- block and time to prove of the effective proof
+ALTER TABLE batch ADD COLUMN propose_l1_timestamp BIGINT;
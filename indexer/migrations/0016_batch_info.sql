@@ -0,0 +1,30 @@
+-- BatchProposed info and meta fields not kept in the batch table
+CREATE TABLE batch_info (
+    batch_id                     BIGINT PRIMARY KEY,
+    info_hash                    TEXT NOT NULL,
+    txs_hash                     TEXT NOT NULL,
+    extra_data                   TEXT NOT NULL,
+    proposed_in                  BIGINT NOT NULL,
+    blob_created_in              BIGINT NOT NULL,
+    blob_byte_offset             BIGINT NOT NULL,
+    blob_byte_size               BIGINT NOT NULL,
+    gas_limit                    BIGINT NOT NULL,
+    last_block_timestamp         BIGINT NOT NULL,
+    anchor_block_id              BIGINT NOT NULL,
+    anchor_block_hash            TEXT NOT NULL,
+    adjustment_quotient          BIGINT NOT NULL,
+    sharing_pctg                 BIGINT NOT NULL,
+    gas_issuance_per_second      BIGINT NOT NULL,
+    min_gas_excess               TEXT NOT NULL,
+    max_gas_issuance_per_block   BIGINT NOT NULL
+);
+CREATE INDEX idx_batch_info_anchor_block_id ON batch_info(anchor_block_id);
+
+-- Blob hashes of every batch in order, indexed by hash to find reused blobs
+CREATE TABLE batch_blob (
+    batch_id    BIGINT NOT NULL,
+    blob_index  BIGINT NOT NULL,
+    blob_hash   TEXT NOT NULL,
+    PRIMARY KEY (batch_id, blob_index)
+);
+CREATE INDEX idx_batch_blob_blob_hash ON batch_blob(blob_hash);
@@ -0,0 +1,14 @@
+-- Every distinct inbox configuration read from pacayaConfig, in force from
+-- l1_block until the next row. Bonds are TEXT as they can exceed BIGINT.
+CREATE TABLE protocol_config (
+    l1_block                  BIGINT PRIMARY KEY,
+    proving_window            BIGINT NOT NULL,
+    cooldown_window           BIGINT NOT NULL,
+    liveness_bond_base        TEXT NOT NULL,
+    liveness_bond_per_block   TEXT NOT NULL,
+    max_blocks_per_batch      BIGINT NOT NULL,
+    ontake_fork_height        BIGINT NOT NULL,
+    pacaya_fork_height        BIGINT NOT NULL,
+    shasta_fork_height        BIGINT NOT NULL,
+    unzen_fork_height         BIGINT NOT NULL
+);
@@ -0,0 +1,22 @@
+-- Protocol fork whose event proposed the batch, batches indexed before were
+-- all proposed on Pacaya
+ALTER TABLE batch ADD COLUMN fork TEXT NOT NULL DEFAULT 'pacaya';
+
+-- Prover named by the proof event, only Ontake proofs carry it
+ALTER TABLE pending_proof ADD COLUMN prover TEXT;
+
+-- Ontake proposals don't include the transaction count of their block.
+-- SQLite can't drop NOT NULL in place, so the table is copied.
+CREATE TABLE l2_block_copy (
+    block_number  BIGINT PRIMARY KEY,
+    batch_id      BIGINT NOT NULL,
+    timestamp     BIGINT NOT NULL,
+    tx_count      BIGINT,
+    signal_count  BIGINT NOT NULL,
+    gas_used      BIGINT,
+    base_fee      TEXT
+);
+INSERT INTO l2_block_copy SELECT * FROM l2_block;
+DROP TABLE l2_block;
+ALTER TABLE l2_block_copy RENAME TO l2_block;
+CREATE INDEX idx_l2_block_batch_id ON l2_block(batch_id);
@@ -0,0 +1,9 @@
+-- Flags are BIGINT 0 or 1 on both backends. is_sent_by_proposer is still
+-- BOOLEAN from the initial schema, which the Any driver can't read on
+-- SQLite, so it is copied into a BIGINT column.
+DROP INDEX idx_batch_sender;
+ALTER TABLE batch ADD COLUMN is_sent_by_proposer_flag BIGINT NOT NULL DEFAULT 0;
+UPDATE batch SET is_sent_by_proposer_flag = CASE WHEN is_sent_by_proposer THEN 1 ELSE 0 END;
+ALTER TABLE batch DROP COLUMN is_sent_by_proposer;
+ALTER TABLE batch RENAME COLUMN is_sent_by_proposer_flag TO is_sent_by_proposer;
+CREATE INDEX idx_batch_sender ON batch(is_sent_by_proposer);
@@ -0,0 +1,14 @@
+-- Integers of the initial schema are INTEGER, which is 32 bit on PostgreSQL.
+-- SQLite integers are already 64 bit, so this runs on PostgreSQL only.
+ALTER TABLE batch
+    ALTER COLUMN batch_id TYPE BIGINT,
+    ALTER COLUMN proposed_at TYPE BIGINT,
+    ALTER COLUMN last_block_id TYPE BIGINT,
+    ALTER COLUMN block_count TYPE BIGINT;
+ALTER TABLE status
+    ALTER COLUMN id TYPE BIGINT,
+    ALTER COLUMN indexed_l1_block TYPE BIGINT,
+    ALTER COLUMN proposed_batch_id TYPE BIGINT,
+    ALTER COLUMN proposed_block_id TYPE BIGINT,
+    ALTER COLUMN proved_batch_id TYPE BIGINT,
+    ALTER COLUMN proved_block_id TYPE BIGINT;
@@ -0,0 +1,31 @@
+-- Views over the latest schema, dropped before pending migrations run and
+-- created again after them, as PostgreSQL expands batch.* and batch_proof.*
+-- when a view is created.
+
+-- Every batch joined with its effective proof
+CREATE VIEW batch_view AS
+SELECT
+    batch.*,
+    batch_proof.prover,
+    batch_proof.prover_attribution,
+    batch_proof.in_proving_window,
+    batch_proof.prove_fee,
+    batch_proof.prove_fee_split,
+    batch_proof.l1_block AS prove_l1_block,
+    batch_proof.l1_timestamp AS prove_l1_timestamp,
+    batch_proof.l1_timestamp - batch.propose_l1_timestamp AS time_to_prove,
+    batch_proof.is_profitable,
+    batch_proof.is_proved_by_proposer,
+    verifier.proof_type
+FROM batch
+LEFT JOIN batch_proof
+    ON batch_proof.batch_id = batch.batch_id AND batch_proof.prove_tx = batch.prove_tx
+LEFT JOIN verifier ON verifier.address = batch_proof.verifier;
+
+-- Every proof with the proof type of its verifier
+CREATE VIEW batch_proof_view AS
+SELECT
+    batch_proof.*,
+    verifier.proof_type
+FROM batch_proof
+LEFT JOIN verifier ON verifier.address = batch_proof.verifier;
@@ -1,303 +1,2317 @@
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map},
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 
 use alloy::{
-    primitives::Address,
-    providers::{DynProvider, Provider, ProviderBuilder},
-    rpc::types::Filter,
+    eips::{BlockId, eip4844::DATA_GAS_PER_BLOB},
+    primitives::{Address, B256, I256, TxHash, U256, address},
+    providers::{DynProvider, Provider},
+    rpc::types::{Filter, Header, Log, TransactionReceipt},
     sol_types::SolEvent,
+    transports::TransportError,
+};
+use anyhow::{Context, Error};
+use futures::stream::{self, StreamExt, TryStreamExt};
+
+use crate::{
+    config::Config,
+    db::{
+        Batch, BatchProof, BatchProposal, BondEvent, BondEventKind, DataBase, PendingProof,
+        ProtocolConfig, ProverAttribution, RangeTx, TxCost,
+    },
+    error::{self, Backoff, IndexerError, retry},
+    fee_split::{self, FeeSplit, ProposeFeeSplit, ProveFeeSplit},
+    fork::{self, Fork, ProposedBatch},
+    head_subscription::HeadSubscription,
+    indexing_step::IndexingStep,
+    migrations::{self, Backfill},
+    range_cache::{L2Block, RangeCache, TransitionKey},
+    rpc_failover::{FailoverTransport, parse_endpoints},
+    verifier_registry::parse_verifier_registry,
 };
-use anyhow::Error;
 
-use crate::{config::Config, db::DataBase};
+use super::taiko_inbox_binding::{IERC1967, ITaikoInbox};
+
+use tokio::time::{Duration, Instant, sleep};
 
-use super::taiko_inbox_binding::ITaikoInbox;
+const WS_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Sender of the anchor transaction that opens every L2 block, it pays no fees
+const GOLDEN_TOUCH_ADDRESS: Address = address!("0x0000777735367b36bC9B61C50022d9D0700dB4Ec");
+/// Batches filled per transaction while backfilling a migration
+const BACKFILL_CHUNK_SIZE: i64 = 100;
 
-use tokio::time::{Duration, sleep};
+/// Header data and fees paid in a single L2 block
+struct L2BlockFees {
+    block_number: u64,
+    gas_used: u64,
+    base_fee: u64,
+    priority_fee: u128,
+    base_fee_share: u128,
+}
+
+/// Events of an L1 range with the contract state needed to index them,
+/// fetched before the range transaction is opened
+struct RangeData {
+    upgraded: Vec<Log>,
+    proposed: Vec<Log>,
+    proved: Vec<Log>,
+    conflicting: Vec<Log>,
+    bond: Vec<Log>,
+    verified: Vec<Log>,
+    /// pacayaConfig after every upgrade and at the periodic refresh
+    protocol_configs: BTreeMap<u64, ProtocolConfig>,
+    /// bondBalanceOf at the end of the range of every address with a bond event
+    bond_balances: BTreeMap<Address, U256>,
+}
 
 pub struct BatchIndexer {
     indexed_l1_block: u64,
+    l1_start_block: u64,
     db: DataBase,
+    l1_transport: FailoverTransport,
     l1_provider: DynProvider,
     l2_provider: DynProvider,
     taiko_inbox: Address,
-    proving_window: u64,
-    indexing_step: u64,
+    protocol_config_refresh_blocks: u64,
+    protocol_config_refreshed_at: u64,
+    head_indexing_step: u64,
+    indexing_step: IndexingStep,
+    range_log_count: AtomicUsize,
     sleep_duration_sec: u64,
     max_l1_fork_depth: u64,
+    retry_max_delay: Duration,
+    rpc_concurrency: usize,
+    use_block_receipts: AtomicBool,
+    cross_check_logs: bool,
+    propose_fee_split: ProposeFeeSplit,
+    prove_fee_split: ProveFeeSplit,
+    l1_ws_url: Option<String>,
+    head_subscription: Option<HeadSubscription>,
+    head_subscription_retry_at: Instant,
+    cache: RangeCache,
 }
 
 impl BatchIndexer {
-    pub async fn new(config: Config) -> Result<Self, Error> {
-        let db = DataBase::new(&config.db_filename).await?;
-        let indexed_l1_block = db.get_indexed_l1_block().await.max(config.l1_start_block);
-        let l1_provider = ProviderBuilder::new()
-            .connect_http(config.l1_rpc_url.parse()?)
-            .erased();
-
-        let taiko_inbox = Address::from_str(config.taiko_inbox_address.as_str())?;
+    pub async fn new(config: Config) -> Result<Self, IndexerError> {
+        let db = DataBase::new(&config.database_url)
+            .await
+            .map_err(IndexerError::Database)?;
+        let verifiers = parse_verifier_registry(&config.verifier_registry)
+            .context("Invalid VERIFIER_REGISTRY")
+            .map_err(IndexerError::Fatal)?;
+        db.replace_verifiers(&verifiers)
+            .await
+            .map_err(IndexerError::Database)?;
+        let indexed_l1_block = db.get_indexed_l1_block().await?.max(config.l1_start_block);
+        let l1_transport = FailoverTransport::new(
+            parse_endpoints(&config.l1_rpc_url)
+                .context("Invalid L1_RPC_URL")
+                .map_err(IndexerError::Fatal)?,
+        );
+        let l1_provider = l1_transport.provider();
+        let cross_check_logs = config.cross_check_logs && l1_transport.endpoint_count() > 1;
+        if config.cross_check_logs && !cross_check_logs {
+            tracing::warn!("CROSS_CHECK_LOGS needs at least two L1 endpoints, disabling it");
+        }
 
-        let ti_contract = ITaikoInbox::new(taiko_inbox, &l1_provider);
-        let pacaya_config = ti_contract.pacayaConfig().call().await?;
-        tracing::info!("Proving window: {}", pacaya_config.provingWindow);
+        let taiko_inbox = Address::from_str(config.taiko_inbox_address.as_str())
+            .context("Invalid TAIKO_INBOX_ADDRESS")
+            .map_err(IndexerError::Fatal)?;
+        let retry_max_delay = Duration::from_secs(config.retry_max_delay_sec);
 
-        let l2_provider = ProviderBuilder::new()
-            .connect_http(config.l2_rpc_url.parse()?)
-            .erased();
+        let l2_provider = FailoverTransport::new(
+            parse_endpoints(&config.l2_rpc_url)
+                .context("Invalid L2_RPC_URL")
+                .map_err(IndexerError::Fatal)?,
+        )
+        .provider();
 
-        Ok(Self {
+        let indexer = Self {
             indexed_l1_block,
+            l1_start_block: config.l1_start_block,
             db,
+            l1_transport,
             l1_provider,
             l2_provider,
             taiko_inbox,
-            proving_window: u64::from(pacaya_config.provingWindow),
-            indexing_step: config.indexing_step,
+            protocol_config_refresh_blocks: config.protocol_config_refresh_blocks,
+            protocol_config_refreshed_at: 0,
+            head_indexing_step: config.indexing_step,
+            indexing_step: IndexingStep::new(
+                config.indexing_step,
+                config.min_indexing_step,
+                config.max_indexing_step,
+            ),
+            range_log_count: AtomicUsize::new(0),
             sleep_duration_sec: config.sleep_duration_sec,
             max_l1_fork_depth: config.max_l1_fork_depth,
+            retry_max_delay,
+            rpc_concurrency: config.rpc_concurrency,
+            use_block_receipts: AtomicBool::new(config.use_block_receipts),
+            cross_check_logs,
+            propose_fee_split: config.propose_fee_split,
+            prove_fee_split: config.prove_fee_split,
+            l1_ws_url: config.l1_ws_url,
+            head_subscription: None,
+            head_subscription_retry_at: Instant::now(),
+            cache: RangeCache::default(),
+        };
+        indexer.init_protocol_config().await?;
+        indexer.run_backfills().await?;
+
+        Ok(indexer)
+    }
+
+    /// Starts the protocol config history with the config read at
+    /// L1_START_BLOCK, or the latest one on a node without historical state,
+    /// followed by the upgrades in the blocks already indexed.
+    /// Changes since the last run are picked up by the first indexed range.
+    async fn init_protocol_config(&self) -> Result<(), IndexerError> {
+        let recorded = retry(
+            "Failed to read protocol config history",
+            self.retry_max_delay,
+            || async {
+                let mut tx = self.db.begin().await?;
+                tx.get_protocol_config(self.indexed_l1_block).await
+            },
+        )
+        .await?;
+        if let Some(config) = recorded {
+            tracing::info!("Proving window: {}", config.proving_window);
+            return Ok(());
+        }
+
+        let config = retry(
+            "Failed to read pacayaConfig",
+            self.retry_max_delay,
+            || async {
+                match self
+                    .read_protocol_config(self.l1_start_block.into())
+                    .await?
+                {
+                    Some(config) => Ok(config),
+                    None => {
+                        tracing::warn!(
+                            "pacayaConfig not readable at L1_START_BLOCK, using the latest config"
+                        );
+                        self.read_protocol_config(BlockId::latest())
+                            .await?
+                            .ok_or_else(|| anyhow::anyhow!("pacayaConfig not readable"))
+                    }
+                }
+            },
+        )
+        .await?;
+        // a database indexed before the history existed has the upgrades of
+        // its indexed blocks recorded too
+        let upgrades = self
+            .get_indexed_upgrade_logs(self.l1_start_block + 1)
+            .await?;
+        let l1_blocks: BTreeSet<u64> = upgrades.iter().filter_map(|log| log.block_number).collect();
+        let configs = retry("Failed to read pacayaConfig", self.retry_max_delay, || {
+            self.read_protocol_configs(l1_blocks.clone())
         })
+        .await?;
+
+        retry(
+            "Failed to record protocol config history",
+            self.retry_max_delay,
+            || async {
+                let mut tx = self.db.begin().await?;
+                tx.insert_protocol_config(self.l1_start_block, &config)
+                    .await?;
+                for log in &upgrades {
+                    self.store_log_block(&mut tx, log).await?;
+                }
+                self.record_protocol_configs(&mut tx, &configs).await?;
+                tx.commit().await
+            },
+        )
+        .await?;
+        tracing::info!("Proving window: {}", config.proving_window);
+
+        Ok(())
     }
 
-    async fn get_current_block_number(&self) -> u64 {
-        let current_block = match self.l1_provider.get_block_number().await {
-            Ok(block) => block,
-            Err(e) => panic!("Failed to get current block number: {e}"),
-        };
-        return current_block.saturating_sub(self.max_l1_fork_depth);
+    /// Upgraded events of the inbox from `from_block` to the last indexed
+    /// block. The scan starts at MAX_INDEXING_STEP and only shrinks the step
+    /// when the provider rejects a range.
+    async fn get_indexed_upgrade_logs(&self, from_block: u64) -> Result<Vec<Log>, IndexerError> {
+        let mut indexing_step = self.indexing_step.widest();
+        let mut backoff = Backoff::new(self.retry_max_delay);
+        let mut logs = Vec::new();
+        let mut from_block = from_block;
+        while from_block <= self.indexed_l1_block {
+            let to_block = (from_block + indexing_step.current() - 1).min(self.indexed_l1_block);
+            match self
+                .get_upgrade_logs(from_block, to_block)
+                .await
+                .map_err(IndexerError::from)
+            {
+                Ok(range_logs) => {
+                    logs.extend(range_logs);
+                    from_block = to_block + 1;
+                }
+                Err(e @ IndexerError::LogRangeTooLarge(_)) if indexing_step.shrink() => {
+                    tracing::warn!(
+                        "{}: {e}, upgrade scan step reduced to {}",
+                        e.kind(),
+                        indexing_step.current()
+                    );
+                }
+                Err(e) if e.is_retryable() => {
+                    let delay = backoff.next_delay(&e);
+                    tracing::warn!(
+                        "Failed to fetch Upgraded events: {}: {e}, retrying in {delay:?}",
+                        e.kind()
+                    );
+                    sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(logs)
+    }
+
+    /// Fills columns added by migrations for batches indexed before they ran.
+    /// Progress is committed per chunk, so an interrupted backfill resumes.
+    async fn run_backfills(&self) -> Result<(), IndexerError> {
+        for (version, _, last_batch_id) in self.db.get_pending_backfills().await? {
+            let backfill = migrations::get_backfill(version).ok_or_else(|| {
+                IndexerError::Fatal(anyhow::anyhow!("Migration {version} has no backfill"))
+            })?;
+            tracing::info!("Backfilling migration {version} up to batch {last_batch_id}");
+            retry("Failed to backfill", self.retry_max_delay, || {
+                self.backfill_batches(version, backfill)
+            })
+            .await?;
+            tracing::info!("Backfill of migration {version} finished");
+        }
+
+        Ok(())
     }
 
-    pub async fn run_indexing_loop(&mut self) {
+    async fn backfill_batches(&self, version: i64, backfill: Backfill) -> Result<(), Error> {
+        // batches are verified in order, none after the last verified one has an event
+        let last_verified_batch_id = match backfill {
+            Backfill::Verification => {
+                let ti_contract = ITaikoInbox::new(self.taiko_inbox, &self.l1_provider);
+                i64::try_from(ti_contract.getStats2().call().await?.lastVerifiedBatchId)?
+            }
+            _ => i64::MAX,
+        };
         loop {
-            let current_block = self.get_current_block_number().await;
-            let from_block = self.indexed_l1_block + 1;
-            let to_block = from_block + self.indexing_step;
-            tracing::info!("Indexing from block {from_block} to block {to_block}");
-            if current_block > to_block {
-                let (proposed_batch_id, proposed_block_id) = self
-                    .index_batch_proposed(from_block, to_block)
-                    .await
-                    .unwrap_or_else(|e| {
-                        panic!(
-                            "Failed to index BatchProposed event (from: {from_block}, to: {to_block}): {e}"
-                        )
-                    });
-                let (proved_batch_id, proved_block_id) = self
-                    .index_batch_proved(from_block, to_block)
-                    .await
-                    .unwrap_or_else(|e| {
-                        panic!(
-                            "Failed to index BatchesProved event (from: {from_block}, to: {to_block}): {e}"
-                        )
-                    });
+            let Some((_, next_batch_id, last_batch_id)) = self
+                .db
+                .get_pending_backfills()
+                .await?
+                .into_iter()
+                .find(|(pending_version, _, _)| *pending_version == version)
+            else {
+                return Ok(());
+            };
+
+            let batches = self
+                .db
+                .get_batches_between(next_batch_id, last_batch_id, BACKFILL_CHUNK_SIZE)
+                .await?;
+            let next_batch_id = batches.last().map_or(last_batch_id, |batch| batch.batch_id) + 1;
+            self.cache.clear();
+            let mut tx = self.db.begin().await?;
+            for batch in batches
+                .into_iter()
+                .filter(|batch| batch.batch_id <= last_verified_batch_id)
+            {
+                self.backfill_batch(&mut tx, backfill, batch).await?;
+            }
+            tx.update_backfill(version, next_batch_id).await?;
+            tx.commit().await?;
+        }
+    }
 
-                self.indexed_l1_block = to_block;
+    async fn backfill_batch(
+        &self,
+        tx: &mut RangeTx,
+        backfill: Backfill,
+        batch: Batch,
+    ) -> Result<(), Error> {
+        match backfill {
+            Backfill::ProposeL1Block => self.backfill_propose_l1_block(tx, batch).await,
+            Backfill::Verification => self.backfill_verification(tx, batch).await,
+            Backfill::BatchProofs => self.backfill_batch_proofs(tx, batch).await,
+            Backfill::L2Blocks => self.backfill_l2_blocks(tx, batch).await,
+            Backfill::TxCosts => self.backfill_tx_costs(tx, batch).await,
+            Backfill::ProposeFeeSplit => self.backfill_propose_fee_split(tx, batch).await,
+            Backfill::ProveFeeSplit => self.backfill_prove_fee_split(tx, batch).await,
+            Backfill::ProposeL1Timestamp => self.backfill_propose_l1_timestamp(tx, batch).await,
+            Backfill::BatchInfo => self.backfill_batch_info(tx, batch).await,
+        }
+    }
 
-                if let Err(e) = self
-                    .db
-                    .update_status(
-                        self.indexed_l1_block,
-                        proposed_batch_id,
-                        proposed_block_id,
-                        proved_batch_id,
-                        proved_block_id,
-                    )
-                    .await
-                {
-                    tracing::error!("Failed to update status: {}", e);
+    async fn backfill_propose_l1_block(&self, tx: &mut RangeTx, batch: Batch) -> Result<(), Error> {
+        let receipt = self
+            .get_receipt(TxHash::from_str(&batch.propose_tx)?)
+            .await?;
+        let propose_l1_block = receipt.block_number.ok_or_else(|| {
+            IndexerError::Rpc(anyhow::anyhow!(
+                "Block number of transaction {} not found",
+                batch.propose_tx
+            ))
+        })?;
+        tx.update_propose_l1_block(batch.batch_id, propose_l1_block)
+            .await
+    }
+
+    /// Scans the indexed L1 blocks for the event that verified the batch.
+    /// Batches are verified in order, so the scan starts at the latest
+    /// verification found for the batches before it. The scan starts at
+    /// MAX_INDEXING_STEP and only shrinks the step when the provider rejects
+    /// a range.
+    async fn backfill_verification(&self, tx: &mut RangeTx, batch: Batch) -> Result<(), Error> {
+        let is_verified =
+            |batch: Option<Batch>| batch.is_some_and(|batch| batch.verified_l1_block.is_some());
+        // an event found for an earlier batch of the chunk may cover it
+        if is_verified(tx.get_batch_by_id(batch.batch_id).await?) {
+            return Ok(());
+        }
+
+        let mut indexing_step = self.indexing_step.widest();
+        let mut from_block = tx
+            .get_last_verified_l1_block()
+            .await?
+            .max(batch.propose_l1_block.try_into()?);
+        while from_block <= self.indexed_l1_block {
+            let to_block = (from_block + indexing_step.current() - 1).min(self.indexed_l1_block);
+            let filter = Filter::new()
+                .address(self.taiko_inbox)
+                .event_signature(fork::signatures(Fork::verified_signature))
+                .from_block(from_block)
+                .to_block(to_block);
+            let logs = match self.get_logs(&filter).await.map_err(IndexerError::from) {
+                Ok(logs) => logs,
+                Err(e @ IndexerError::LogRangeTooLarge(_)) if indexing_step.shrink() => {
+                    tracing::warn!(
+                        "{}: {e}, verification scan step reduced to {}",
+                        e.kind(),
+                        indexing_step.current()
+                    );
+                    continue;
                 }
+                Err(e) => return Err(e.into()),
+            };
+            self.prefetch_headers(&logs).await?;
+            let verified_batch_id = self.index_batches_verified(tx, &logs).await?;
+            tx.update_status(0, 0, 0, 0, 0, verified_batch_id).await?;
+            if is_verified(tx.get_batch_by_id(batch.batch_id).await?) {
+                break;
             }
+            from_block = to_block + 1;
+        }
 
-            let current_block = self.get_current_block_number().await;
-            if self.indexed_l1_block + self.indexing_step > current_block {
-                sleep(Duration::from_secs(
-                    self.indexing_step * self.sleep_duration_sec,
-                ))
-                .await;
-            } else {
-                sleep(Duration::from_secs(self.sleep_duration_sec)).await;
+        Ok(())
+    }
+
+    /// Stores the proofs of the batch found in its proof transaction, the one
+    /// the batch points to is the effective proof
+    async fn backfill_batch_proofs(&self, tx: &mut RangeTx, batch: Batch) -> Result<(), Error> {
+        let Some(prove_tx) = batch.prove_tx.clone() else {
+            return Ok(());
+        };
+        let receipt = self.get_receipt(TxHash::from_str(&prove_tx)?).await?;
+        let prove_fees = self.split_prove_fee(tx, &receipt).await?;
+        for log in receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| log.address() == self.taiko_inbox)
+        {
+            let Some(fork) = fork::fork_of_log(log, Fork::proved_signature) else {
+                continue;
+            };
+            let prove_l1_block = log
+                .block_number
+                .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Log block number not found")))?;
+            let proved_at = self.get_l1_timestamp(prove_l1_block).await?;
+            for proof in Self::get_event_proofs(log, fork, proved_at, &receipt, &prove_fees)? {
+                if proof.batch_id == batch.batch_id {
+                    return self.apply_proof(tx, batch, &proof).await;
+                }
             }
         }
+
+        Err(IndexerError::Rpc(anyhow::anyhow!(
+            "Proof event of batch {} not found in transaction {}",
+            batch.batch_id,
+            prove_tx
+        ))
+        .into())
     }
 
-    pub async fn index_batch_proposed(
+    async fn backfill_l2_blocks(&self, tx: &mut RangeTx, mut batch: Batch) -> Result<(), Error> {
+        let proposed = self.get_batch_proposed(&batch).await?;
+        let sharing_pctg = proposed.base_fee_config.sharing_pctg;
+        batch.base_fee_sharing_pctg = Some(i64::from(sharing_pctg));
+        tx.insert_l2_blocks(&proposed).await?;
+
+        // proved batches earned their revenue with the old balance based estimate
+        if batch.l2_fee_earned.is_some() {
+            let l2_fee_earned = self
+                .calculate_l2_fee_revenue(tx, &mut batch, sharing_pctg)
+                .await?;
+            tx.update_proof_profitability(
+                batch.batch_id,
+                l2_fee_earned,
+                batch
+                    .propose_fee
+                    .parse::<u128>()
+                    .context("Failed to parse propose fee")?,
+            )
+            .await?;
+            batch.l2_fee_earned = Some(l2_fee_earned.to_string());
+        } else {
+            self.store_l2_block_gas(tx, proposed.first_block_id()?, proposed.last_block_id)
+                .await?;
+        }
+
+        tx.update_batch(batch).await
+    }
+
+    async fn backfill_tx_costs(&self, tx: &mut RangeTx, batch: Batch) -> Result<(), Error> {
+        let mut tx_hashes = vec![batch.propose_tx];
+        tx_hashes.extend(tx.get_prove_txs(batch.batch_id).await?);
+        for tx_hash in tx_hashes {
+            let receipt = self.get_receipt(TxHash::from_str(&tx_hash)?).await?;
+            self.store_tx_cost(tx, &receipt).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn backfill_propose_fee_split(
         &self,
-        from_block: u64,
-        to_block: u64,
-    ) -> Result<(u64, u64), Error> {
-        let batch_proposed = ITaikoInbox::BatchProposed::SIGNATURE_HASH;
-        let filter = Filter::new()
-            .address(self.taiko_inbox)
-            .event_signature(batch_proposed)
-            .from_block(from_block)
-            .to_block(to_block);
-        let logs = self.l1_provider.get_logs(&filter).await?;
-        tracing::debug!("Found {} BatchProposed Events", logs.len());
+        tx: &mut RangeTx,
+        batch: Batch,
+    ) -> Result<(), Error> {
+        let receipt = self
+            .get_receipt(TxHash::from_str(&batch.propose_tx)?)
+            .await?;
+        let (split, propose_fees) = self.split_propose_fee(&receipt)?;
+        let batch_id: u64 = batch.batch_id.try_into()?;
+        let propose_fee = propose_fees.get(&batch_id).copied().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Proposal event of batch {} not found in transaction {}",
+                batch.batch_id,
+                batch.propose_tx
+            )
+        })?;
+        tx.update_propose_fee(batch.batch_id, propose_fee, split)
+            .await?;
 
-        let mut propsed_batch_id = 0;
-        let mut proposed_block_id = 0;
+        if let Some(l2_fee_earned) = &batch.l2_fee_earned {
+            tx.update_proof_profitability(batch.batch_id, l2_fee_earned.parse()?, propose_fee)
+                .await?;
+        }
 
-        for log in logs {
-            let receipt = match self
-                .l1_provider
-                .get_transaction_receipt(
-                    log.transaction_hash.expect("Transaction receipt not found"),
-                )
-                .await?
-            {
-                Some(receipt) => receipt,
-                None => panic!(
-                    "Transaction receipt not found for {:?}",
-                    log.transaction_hash
-                ),
-            };
-            let propose_fee = Self::get_tx_eth_price(&receipt);
-            let batch = log.log_decode::<ITaikoInbox::BatchProposed>()?;
-
-            propsed_batch_id = propsed_batch_id.max(batch.inner.meta.batchId);
-            proposed_block_id = proposed_block_id.max(batch.inner.info.lastBlockId);
-            self.db
-                .insert_batch(
-                    batch,
-                    log.transaction_hash
-                        .expect("proposeBatch transaction hash not found")
-                        .to_string(),
-                    receipt.from,
-                    propose_fee,
+        Ok(())
+    }
+
+    async fn backfill_prove_fee_split(&self, tx: &mut RangeTx, batch: Batch) -> Result<(), Error> {
+        let batch_id: u64 = batch.batch_id.try_into()?;
+        for prove_tx in tx.get_prove_txs(batch.batch_id).await? {
+            let receipt = self.get_receipt(TxHash::from_str(&prove_tx)?).await?;
+            let (split, prove_fees) = self.split_prove_fee(tx, &receipt).await?;
+            let prove_fee = prove_fees.get(&batch_id).copied().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Proof event of batch {} not found in transaction {}",
+                    batch.batch_id,
+                    prove_tx
                 )
+            })?;
+            tx.update_prove_fee(batch.batch_id, &prove_tx, prove_fee, split)
                 .await?;
         }
 
-        Ok((propsed_batch_id, proposed_block_id))
+        if let Some(l2_fee_earned) = &batch.l2_fee_earned {
+            tx.update_proof_profitability(
+                batch.batch_id,
+                l2_fee_earned.parse()?,
+                batch
+                    .propose_fee
+                    .parse::<u128>()
+                    .context("Failed to parse propose fee")?,
+            )
+            .await?;
+        }
+
+        Ok(())
     }
 
-    pub async fn index_batch_proved(
+    async fn backfill_propose_l1_timestamp(
         &self,
-        from_block: u64,
-        to_block: u64,
-    ) -> Result<(u64, u64), Error> {
-        let batches_proved = ITaikoInbox::BatchesProved::SIGNATURE_HASH;
+        tx: &mut RangeTx,
+        batch: Batch,
+    ) -> Result<(), Error> {
+        let propose_l1_timestamp = self
+            .get_l1_timestamp(batch.propose_l1_block.try_into()?)
+            .await?;
+        tx.update_propose_l1_timestamp(batch.batch_id, propose_l1_timestamp)
+            .await
+    }
+
+    async fn backfill_batch_info(&self, tx: &mut RangeTx, batch: Batch) -> Result<(), Error> {
+        let proposed = self.get_batch_proposed(&batch).await?;
+        tx.insert_batch_info(&proposed).await
+    }
+
+    /// Fetches the proposal event of an indexed batch again, decoded by the
+    /// fork it was proposed on
+    async fn get_batch_proposed(&self, batch: &Batch) -> Result<ProposedBatch, Error> {
+        let fork = Fork::from_str(&batch.fork)?;
+        let signature = fork.proposed_signature().ok_or_else(|| {
+            anyhow::anyhow!("Proposals of the {} fork are not supported", fork.as_str())
+        })?;
+        let propose_l1_block: u64 = batch.propose_l1_block.try_into()?;
         let filter = Filter::new()
             .address(self.taiko_inbox)
-            .event_signature(batches_proved)
-            .from_block(from_block)
-            .to_block(to_block);
-        let logs = self.l1_provider.get_logs(&filter).await?;
-        tracing::debug!("Found {} BatchesProved Events", logs.len());
+            .event_signature(signature)
+            .from_block(propose_l1_block)
+            .to_block(propose_l1_block);
+        for log in self.get_logs(&filter).await? {
+            let proposed = fork.decode_proposed(&log)?;
+            if i64::try_from(proposed.batch_id)? == batch.batch_id {
+                return Ok(proposed);
+            }
+        }
 
-        let mut proved_batch_id = 0;
-        let mut proved_block_id = 0u64;
+        Err(IndexerError::Rpc(anyhow::anyhow!(
+            "Proposal event of batch {} not found in L1 block {}",
+            batch.batch_id,
+            propose_l1_block
+        ))
+        .into())
+    }
 
-        for log in logs {
-            let batches = log.log_decode::<ITaikoInbox::BatchesProved>()?;
-            let receipt = self
-                .l1_provider
-                .get_transaction_receipt(
-                    log.transaction_hash
-                        .expect("proveBatch transaction receipt not found"),
-                )
-                .await?
-                .expect("proveBatch transaction receipt is None");
-            tracing::debug!("Proved {} batches", batches.inner.batchIds.len());
-
-            let tx_hash = log
-                .transaction_hash
-                .expect("proveBatch transaction hash not found")
-                .to_string();
-            // we divide the total fee by the number of batches to get the prove fee
-            let prove_fee = Self::get_tx_eth_price(&receipt) / batches.inner.batchIds.len() as u128;
-
-            for batch_id in batches.inner.batchIds.clone() {
-                if let Some(mut batch) = self.db.get_batch_by_id(batch_id.try_into()?).await {
-                    proved_batch_id = proved_batch_id.max(batch_id);
-                    proved_block_id = proved_block_id.max(batch.last_block_id.try_into()?);
+    async fn get_current_block_number(&self) -> Result<u64, Error> {
+        let current_block = self
+            .l1_provider
+            .get_block_number()
+            .await
+            .context("Failed to get current block number")?;
+        Ok(current_block.saturating_sub(self.max_l1_fork_depth))
+    }
 
-                    batch.prove_tx = Some(tx_hash.clone());
-                    batch.prove_fee = Some(prove_fee.to_string());
-                    let prover = self
-                        .get_prover(
-                            receipt.from.to_string().as_str(),
-                            batch.sender.as_str(),
-                            receipt.block_number.expect("receipt block number is None"),
-                            batch.proposed_at.try_into()?,
-                        )
-                        .await?;
-                    batch.is_proved_by_proposer = Some(prover == batch.proposer);
-                    batch.prover = Some(prover);
-                    let l2_fee_earned = self
-                        .calculate_l2_fee_earned(
-                            batch.coinbase.as_str(),
-                            batch.last_block_id.try_into()?,
-                            batch.block_count.try_into()?,
-                        )
-                        .await?;
-                    batch.l2_fee_earned = Some(l2_fee_earned.to_string());
-                    batch.is_profitable = Some(
-                        l2_fee_earned
-                            > prove_fee
-                                + batch
-                                    .propose_fee
-                                    .parse::<u128>()
-                                    .expect("Failed to parse propose fee {}"),
+    async fn get_l1_header(&self, block_number: u64) -> Result<Header, Error> {
+        let block = self
+            .l1_provider
+            .get_block_by_number(block_number.into())
+            .await?
+            .ok_or_else(|| {
+                IndexerError::Rpc(anyhow::anyhow!("L1 block {block_number} not found"))
+            })?;
+        Ok(block.header)
+    }
+
+    async fn get_l1_block_hash(&self, block_number: u64) -> Result<String, Error> {
+        Ok(self.get_l1_header(block_number).await?.hash.to_string())
+    }
+
+    /// Compares the hash of the last indexed L1 block with the chain and, if it
+    /// has been reorged out, rewinds the database to the newest stored block
+    /// that is still canonical.
+    async fn handle_l1_reorg(&mut self) -> Result<(), Error> {
+        let Some(stored_hash) = self.db.get_l1_block_hash(self.indexed_l1_block).await? else {
+            return Ok(());
+        };
+        if self.get_l1_block_hash(self.indexed_l1_block).await? == stored_hash {
+            return Ok(());
+        }
+
+        tracing::warn!("L1 reorg detected at block {}", self.indexed_l1_block);
+
+        let mut common_block = self.l1_start_block;
+        for (block_number, block_hash) in
+            self.db.get_l1_blocks_before(self.indexed_l1_block).await?
+        {
+            if block_number <= self.l1_start_block {
+                break;
+            }
+            if self.get_l1_block_hash(block_number).await? == block_hash {
+                common_block = block_number;
+                break;
+            }
+        }
+
+        tracing::warn!("Rolling back to L1 block {}", common_block);
+        self.db.rollback(common_block).await?;
+        self.indexed_l1_block = common_block;
+        if let Some(subscription) = self.head_subscription.as_mut() {
+            subscription.clear_logs();
+        }
+        // configs recorded after the common block are gone, read it again
+        self.protocol_config_refreshed_at = 0;
+
+        Ok(())
+    }
+
+    /// Indexes L1 until a non retryable error occurs and returns that error
+    pub async fn run_indexing_loop(&mut self) -> IndexerError {
+        let mut backoff = Backoff::new(self.retry_max_delay);
+        loop {
+            let delay = match self.index_next_range().await {
+                Ok(delay) => {
+                    backoff.reset();
+                    self.wait_for_next_range(delay).await;
+                    continue;
+                }
+                Err(e @ IndexerError::LogRangeTooLarge(_)) if self.indexing_step.shrink() => {
+                    tracing::warn!(
+                        "{}: {e}, indexing step reduced to {}",
+                        e.kind(),
+                        self.indexing_step.current()
                     );
+                    Duration::ZERO
+                }
+                Err(e) if e.is_retryable() => {
+                    let delay = backoff.next_delay(&e);
+                    tracing::warn!("{}: {e}, retrying in {delay:?}", e.kind());
+                    delay
+                }
+                Err(e) => return e,
+            };
+            sleep(delay).await;
+        }
+    }
 
-                    self.db.update_batch(batch).await?;
-                } else {
-                    tracing::error!("Batch with id {} not found", batch_id);
+    /// Sleeps for `delay`, waking up early on a new L1 head while subscribed. A dropped subscription falls back to polling until it can be
+    /// reconnected.
+    async fn wait_for_next_range(&mut self, delay: Duration) {
+        self.ensure_head_subscription().await;
+        let Some(subscription) = self.head_subscription.as_mut() else {
+            sleep(delay).await;
+            return;
+        };
+        tokio::select! {
+            result = subscription.next_block() => {
+                if let Err(e) = result {
+                    tracing::warn!("{e:#}, falling back to polling");
+                    self.drop_head_subscription();
                 }
             }
+            () = sleep(delay) => {}
+        }
+    }
+
+    async fn ensure_head_subscription(&mut self) {
+        let Some(url) = self.l1_ws_url.as_deref() else {
+            return;
+        };
+        if self.head_subscription.is_some() || Instant::now() < self.head_subscription_retry_at {
+            return;
+        }
+        let connect = HeadSubscription::connect(url, self.taiko_inbox);
+        match tokio::time::timeout(WS_CONNECT_TIMEOUT, connect).await {
+            Ok(Ok(subscription)) => {
+                tracing::info!("Subscribed to L1 heads and inbox logs over WebSocket");
+                self.head_subscription = Some(subscription);
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to subscribe over WebSocket: {e:#}, polling instead");
+                self.drop_head_subscription();
+            }
+            Err(_) => {
+                tracing::warn!("Timed out subscribing over WebSocket, polling instead");
+                self.drop_head_subscription();
+            }
+        }
+    }
+
+    fn drop_head_subscription(&mut self) {
+        self.head_subscription = None;
+        self.head_subscription_retry_at = Instant::now() + self.retry_max_delay;
+    }
+
+    /// Indexes the next range if it is deep enough and returns how long to
+    /// wait before the next one
+    async fn index_next_range(&mut self) -> Result<Duration, IndexerError> {
+        self.handle_l1_reorg()
+            .await
+            .context("Failed to check L1 reorg")?;
+
+        let current_block = self.get_current_block_number().await?;
+        let from_block = self.indexed_l1_block + 1;
+        let range_end = if self.head_subscription.is_some() {
+            // blocks are indexed as soon as they are MAX_L1_FORK_DEPTH deep
+            (current_block >= from_block).then_some(current_block)
+        } else {
+            // wait for at least INDEXING_STEP blocks near the head, but index up
+            // to the adaptive step when behind
+            (current_block > from_block + self.head_indexing_step).then(|| current_block - 1)
+        };
+        if let Some(range_end) = range_end {
+            let to_block = (from_block + self.indexing_step.current()).min(range_end);
+            tracing::info!("Indexing from block {from_block} to block {to_block}");
+
+            let started = Instant::now();
+            self.range_log_count.store(0, Ordering::Relaxed);
+            self.index_range(from_block, to_block).await?;
+            self.indexing_step.on_success(
+                started.elapsed(),
+                self.range_log_count.load(Ordering::Relaxed),
+            );
+        }
+
+        let current_block = self.get_current_block_number().await?;
+        if self.indexed_l1_block + self.head_indexing_step > current_block {
+            Ok(Duration::from_secs(
+                self.head_indexing_step * self.sleep_duration_sec,
+            ))
+        } else {
+            Ok(Duration::from_secs(self.sleep_duration_sec))
+        }
+    }
+
+    async fn index_range(&mut self, from_block: u64, to_block: u64) -> Result<(), Error> {
+        self.cache.clear();
+
+        // fetch the checkpoint hash before the logs so that a reorg in between
+        // is detected on the next iteration
+        let to_block_hash = self.get_l1_block_hash(to_block).await?;
+        // the config is read every PROTOCOL_CONFIG_REFRESH_BLOCKS blocks in
+        // case it changed without an upgrade
+        let refresh_protocol_config =
+            to_block >= self.protocol_config_refreshed_at + self.protocol_config_refresh_blocks;
+        let pushed_logs = self.get_pushed_logs(from_block, to_block);
+        let range = self
+            .fetch_range(from_block, to_block, refresh_protocol_config, pushed_logs)
+            .await
+            .with_context(|| {
+                format!("Failed to fetch range (from: {from_block}, to: {to_block})")
+            })?;
+
+        // the transaction holds the only database connection, so it is opened
+        // once everything the range needs has been fetched
+        self.cache.seal();
+        let mut tx = self.db.begin().await?;
+        // batches of the range are evaluated against the config recorded first
+        self.index_protocol_config(&mut tx, &range)
+            .await
+            .with_context(|| {
+                format!("Failed to index protocol config (from: {from_block}, to: {to_block})")
+            })?;
+        let (proposed_batch_id, proposed_block_id) = self
+            .index_batch_proposed(&mut tx, &range.proposed)
+            .await
+            .with_context(|| {
+                format!("Failed to index proposal events (from: {from_block}, to: {to_block})")
+            })?;
+        self.check_fork_support(&mut tx, to_block)
+            .await
+            .context("Failed to check protocol fork")?;
+        let (pending_batch_id, pending_block_id) = self
+            .apply_pending_proofs(&mut tx)
+            .await
+            .context("Failed to apply pending proofs")?;
+        let (proved_batch_id, proved_block_id) = self
+            .index_batch_proved(&mut tx, &range.proved)
+            .await
+            .with_context(|| {
+                format!("Failed to index proof events (from: {from_block}, to: {to_block})")
+            })?;
+        let proved_batch_id = proved_batch_id.max(pending_batch_id);
+        let proved_block_id = proved_block_id.max(pending_block_id);
+
+        self.index_conflicting_proofs(&mut tx, &range.conflicting)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to index ConflictingProof event (from: {from_block}, to: {to_block})"
+                )
+            })?;
+
+        self.index_bond_events(&mut tx, &range.bond, &range.bond_balances, to_block)
+            .await
+            .with_context(|| {
+                format!("Failed to index bond events (from: {from_block}, to: {to_block})")
+            })?;
+
+        let verified_batch_id = self
+            .index_batches_verified(&mut tx, &range.verified)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to index BatchesVerified event (from: {from_block}, to: {to_block})"
+                )
+            })?;
+
+        tx.insert_l1_block(to_block, to_block_hash)
+            .await
+            .context("Failed to store L1 block hash")?;
+        tx.update_status(
+            to_block,
+            proposed_batch_id,
+            proposed_block_id,
+            proved_batch_id,
+            proved_block_id,
+            verified_batch_id,
+        )
+        .await
+        .context("Failed to update status")?;
+        tx.commit()
+            .await
+            .context("Failed to commit indexed range")?;
+        self.indexed_l1_block = to_block;
+        if refresh_protocol_config {
+            self.protocol_config_refreshed_at = to_block;
+        }
+        if let Some(subscription) = self.head_subscription.as_mut() {
+            subscription.prune_logs(to_block);
+        }
+
+        Ok(())
+    }
+
+    /// Inbox logs of the range pushed over WebSocket, None when they don't
+    /// cover the whole range or logs are cross-checked between endpoints
+    fn get_pushed_logs(&mut self, from_block: u64, to_block: u64) -> Option<Vec<Log>> {
+        if self.cross_check_logs {
+            return None;
+        }
+        match self
+            .head_subscription
+            .as_mut()?
+            .get_logs(from_block, to_block)
+        {
+            Ok(logs) => logs,
+            Err(e) => {
+                tracing::warn!("{e:#}, falling back to polling");
+                self.drop_head_subscription();
+                None
+            }
         }
-        Ok((proved_batch_id, proved_block_id))
     }
 
-    async fn calculate_l2_fee_earned(
+    /// Signatures of the Upgraded, proposal, proof, ConflictingProof, bond
+    /// and verification events of a range
+    fn range_event_signatures() -> [Vec<B256>; 6] {
+        [
+            vec![IERC1967::Upgraded::SIGNATURE_HASH],
+            fork::signatures(Fork::proposed_signature),
+            fork::signatures(Fork::proved_signature),
+            vec![ITaikoInbox::ConflictingProof::SIGNATURE_HASH],
+            vec![
+                ITaikoInbox::BondDeposited::SIGNATURE_HASH,
+                ITaikoInbox::BondWithdrawn::SIGNATURE_HASH,
+                ITaikoInbox::BondCredited::SIGNATURE_HASH,
+                ITaikoInbox::BondDebited::SIGNATURE_HASH,
+            ],
+            fork::signatures(Fork::verified_signature),
+        ]
+    }
+
+    /// Fetches the events of the range with their receipts and blocks, and
+    /// the contract and L2 state needed to index them. Events are taken from
+    /// `pushed_logs` when the subscription received all of them.
+    async fn fetch_range(
         &self,
-        coinbase_address: &str,
-        last_block_number: u64,
-        block_count: u64,
-    ) -> Result<u128, Error> {
-        let coinbase = Address::from_str(coinbase_address)?;
+        from_block: u64,
+        to_block: u64,
+        refresh_protocol_config: bool,
+        pushed_logs: Option<Vec<Log>>,
+    ) -> Result<RangeData, Error> {
+        let signatures = Self::range_event_signatures();
+        let mut logs: [Vec<Log>; 6] = Default::default();
+        match pushed_logs {
+            Some(pushed_logs) => {
+                self.range_log_count
+                    .fetch_add(pushed_logs.len(), Ordering::Relaxed);
+                for log in pushed_logs {
+                    let topic = log.topic0().copied().unwrap_or_default();
+                    if let Some(index) = signatures
+                        .iter()
+                        .position(|signatures| signatures.contains(&topic))
+                    {
+                        logs[index].push(log);
+                    }
+                }
+            }
+            None => {
+                let filter = Filter::new()
+                    .address(self.taiko_inbox)
+                    .from_block(from_block)
+                    .to_block(to_block);
+                for (logs, signatures) in logs.iter_mut().zip(signatures) {
+                    *logs = self
+                        .get_logs(&filter.clone().event_signature(signatures))
+                        .await?;
+                }
+            }
+        }
+        let [upgraded, proposed, proved, conflicting, bond, verified] = logs;
+        tracing::debug!(
+            "Found {} Upgraded, {} proposal, {} proof, {} ConflictingProof, {} bond and {} verification events",
+            upgraded.len(),
+            proposed.len(),
+            proved.len(),
+            conflicting.len(),
+            bond.len(),
+            verified.len()
+        );
+
+        self.prefetch_receipts(&[proposed.as_slice(), proved.as_slice()].concat())
+            .await?;
+        self.prefetch_headers(
+            &[
+                proposed.as_slice(),
+                proved.as_slice(),
+                bond.as_slice(),
+                verified.as_slice(),
+            ]
+            .concat(),
+        )
+        .await?;
+        self.prefetch_proof_state(&proposed, &proved).await?;
+        self.prefetch_l2_headers(&proposed).await?;
 
-        let start_block_number = last_block_number.saturating_sub(block_count);
+        let mut config_blocks: BTreeSet<u64> =
+            upgraded.iter().filter_map(|log| log.block_number).collect();
+        if refresh_protocol_config {
+            config_blocks.insert(to_block);
+        }
+        let protocol_configs = self.read_protocol_configs(config_blocks).await?;
+        let bond_balances = self.read_bond_balances(&bond, to_block).await?;
 
-        let balance_start = self
-            .l2_provider
-            .get_balance(coinbase)
-            .block_id(start_block_number.into())
+        Ok(RangeData {
+            upgraded,
+            proposed,
+            proved,
+            conflicting,
+            bond,
+            verified,
+            protocol_configs,
+            bond_balances,
+        })
+    }
+
+    /// Fetches the transitions of the proofs the range applies, and the L2
+    /// blocks of the batches they prove whose revenue is not known yet, or
+    /// whose gas used splits the fee of a proof transaction
+    async fn prefetch_proof_state(&self, proposed: &[Log], proved: &[Log]) -> Result<(), Error> {
+        let mut proposed_batches = BTreeMap::new();
+        for log in proposed {
+            if let Some(fork) = fork::fork_of_log(log, Fork::proposed_signature) {
+                let batch = fork.decode_proposed(log)?;
+                proposed_batches.insert(batch.batch_id, batch);
+            }
+        }
+
+        let mut transitions = BTreeSet::new();
+        let mut batch_ids = BTreeSet::new();
+        // batches whose L2 gas used weighs the fee split of a proof transaction
+        let mut weighted_batch_ids = BTreeSet::new();
+        for log in proved {
+            let Some(fork) = fork::fork_of_log(log, Fork::proved_signature) else {
+                continue;
+            };
+            let l1_block = log
+                .block_number
+                .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Log block number not found")))?;
+            for transition in fork.decode_proved(log)?.transitions {
+                batch_ids.insert(transition.batch_id);
+                // only Pacaya proofs have their transition stored with the prover
+                if transition.prover.is_none() {
+                    transitions.insert((transition.batch_id, l1_block, transition.parent_hash));
+                }
+            }
+            if self.prove_fee_split == ProveFeeSplit::L2Gas {
+                let receipt = self.get_receipt(Self::get_log_tx_hash(log)?).await?;
+                weighted_batch_ids.extend(self.get_proved_batch_ids(&receipt)?);
+            }
+        }
+        // pending proofs are applied once their batch is indexed, with the fee
+        // of their transaction split again
+        for proof in self.db.get_pending_proofs().await? {
+            let batch_id: u64 = proof.batch_id.try_into()?;
+            if proposed_batches.contains_key(&batch_id)
+                || self.db.get_batch_by_id(proof.batch_id).await?.is_some()
+            {
+                let receipt = self.get_receipt(TxHash::from_str(&proof.prove_tx)?).await?;
+                if self.prove_fee_split == ProveFeeSplit::L2Gas {
+                    weighted_batch_ids.extend(self.get_proved_batch_ids(&receipt)?);
+                }
+                batch_ids.insert(batch_id);
+                if proof.prover.is_none() {
+                    transitions.insert((
+                        batch_id,
+                        proof.l1_block.try_into()?,
+                        B256::from_str(&proof.parent_hash)?,
+                    ));
+                }
+            }
+        }
+        stream::iter(transitions)
+            .map(|key| self.get_cached_transition(key))
+            .buffer_unordered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
             .await?;
 
-        let balance_end = self
-            .l2_provider
-            .get_balance(coinbase)
-            .block_id(last_block_number.into())
+        let mut l2_blocks = BTreeSet::new();
+        for batch_id in batch_ids.union(&weighted_batch_ids) {
+            let (first_block_id, last_block_id) = match proposed_batches.get(batch_id) {
+                Some(batch) => (batch.first_block_id()?, batch.last_block_id),
+                None => match self.db.get_batch_by_id((*batch_id).try_into()?).await? {
+                    Some(batch)
+                        if batch.l2_fee_earned.is_none()
+                            || weighted_batch_ids.contains(batch_id) =>
+                    {
+                        let last_block_id: u64 = batch.last_block_id.try_into()?;
+                        let block_count: u64 = batch.block_count.try_into()?;
+                        (
+                            (last_block_id + 1).saturating_sub(block_count),
+                            last_block_id,
+                        )
+                    }
+                    // unknown batches stay pending, proved ones have their revenue
+                    _ => continue,
+                },
+            };
+            l2_blocks.extend(first_block_id..=last_block_id);
+        }
+        stream::iter(l2_blocks)
+            .map(|block_number| self.get_l2_block(block_number))
+            .buffer_unordered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
             .await?;
 
-        let earned = balance_end.saturating_sub(balance_start);
+        Ok(())
+    }
+
+    /// Fetches the headers of the L2 blocks of the batches proposed in the
+    /// range, whose gas used and base fee are stored with them
+    async fn prefetch_l2_headers(&self, proposed: &[Log]) -> Result<(), Error> {
+        let mut l2_blocks = BTreeSet::new();
+        for log in proposed {
+            if let Some(fork) = fork::fork_of_log(log, Fork::proposed_signature) {
+                let batch = fork.decode_proposed(log)?;
+                l2_blocks.extend(batch.first_block_id()?..=batch.last_block_id);
+            }
+        }
+        stream::iter(l2_blocks)
+            .map(|block_number| self.get_l2_header(block_number))
+            .buffer_unordered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
 
-        Ok(earned.try_into().unwrap_or(0))
+        Ok(())
     }
 
-    async fn get_prover(
+    /// Reads bondBalanceOf at `l1_block` for every address with a bond event
+    async fn read_bond_balances(
         &self,
-        prove_sender: &str,
-        propose_sender: &str,
-        prove_block: u64,
-        proposed_at: u64,
-    ) -> Result<String, Error> {
-        if let Some(block) = self
-            .l1_provider
-            .get_block_by_number(prove_block.into())
-            .await?
-        {
-            if block.header.inner.timestamp > proposed_at + self.proving_window {
-                Ok(prove_sender.to_string())
-            } else {
-                Ok(propose_sender.to_string())
+        logs: &[Log],
+        l1_block: u64,
+    ) -> Result<BTreeMap<Address, U256>, Error> {
+        let ti_contract = ITaikoInbox::new(self.taiko_inbox, &self.l1_provider);
+        let mut balances = BTreeMap::new();
+        for log in logs {
+            let Some((address, _, _)) = Self::decode_bond_event(log)? else {
+                continue;
+            };
+            if let btree_map::Entry::Vacant(entry) = balances.entry(address) {
+                entry.insert(
+                    ti_contract
+                        .bondBalanceOf(address)
+                        .block(l1_block.into())
+                        .call()
+                        .await?,
+                );
             }
-        } else {
-            panic!("Prove block {prove_block} not found");
         }
+
+        Ok(balances)
+    }
+
+    /// Records the protocol config read after every upgrade of the inbox in
+    /// the range and at the periodic refresh
+    async fn index_protocol_config(
+        &self,
+        tx: &mut RangeTx,
+        range: &RangeData,
+    ) -> Result<(), Error> {
+        for log in &range.upgraded {
+            self.store_log_block(tx, log).await?;
+        }
+        self.record_protocol_configs(tx, &range.protocol_configs)
+            .await
+    }
+
+    /// Upgraded events of the inbox in the range
+    async fn get_upgrade_logs(&self, from_block: u64, to_block: u64) -> Result<Vec<Log>, Error> {
+        let filter = Filter::new()
+            .address(self.taiko_inbox)
+            .event_signature(IERC1967::Upgraded::SIGNATURE_HASH)
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self.get_logs(&filter).await?;
+        tracing::debug!("Found {} Upgraded Events", logs.len());
+
+        Ok(logs)
+    }
+
+    /// Reads pacayaConfig at every block where it is readable
+    async fn read_protocol_configs(
+        &self,
+        l1_blocks: BTreeSet<u64>,
+    ) -> Result<BTreeMap<u64, ProtocolConfig>, Error> {
+        let mut configs = BTreeMap::new();
+        for l1_block in l1_blocks {
+            if let Some(config) = self.read_protocol_config(l1_block.into()).await? {
+                configs.insert(l1_block, config);
+            }
+        }
+
+        Ok(configs)
+    }
+
+    /// Records each config where it differs from the one in force
+    async fn record_protocol_configs(
+        &self,
+        tx: &mut RangeTx,
+        configs: &BTreeMap<u64, ProtocolConfig>,
+    ) -> Result<(), Error> {
+        for (l1_block, config) in configs {
+            if tx.get_protocol_config(*l1_block).await?.as_ref() != Some(config) {
+                tracing::info!(
+                    "Protocol config changed at L1 block {l1_block}, proving window: {}",
+                    config.proving_window
+                );
+                tx.insert_protocol_config(*l1_block, config).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops the indexer before the range is committed when the next L2 block
+    /// to propose is in a fork whose events can't be decoded, as its batches
+    /// would be skipped
+    async fn check_fork_support(&self, tx: &mut RangeTx, to_block: u64) -> Result<(), Error> {
+        let Some(config) = tx.get_protocol_config(to_block).await? else {
+            return Ok(());
+        };
+        let next_block_id = tx.get_proposed_block_id().await? + 1;
+        let fork = Fork::of_block(next_block_id, &config);
+        if !Fork::DECODABLE.contains(&fork) {
+            return Err(IndexerError::Fatal(anyhow::anyhow!(
+                "L2 block {next_block_id} is in the {} fork, whose events are not supported",
+                fork.as_str()
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Warns when a batch was proposed with the events of another fork than
+    /// the one its blocks are in according to the fork heights
+    async fn check_batch_fork(
+        &self,
+        tx: &mut RangeTx,
+        batch: &ProposedBatch,
+        propose_l1_block: u64,
+    ) -> Result<(), Error> {
+        let Some(config) = tx.get_protocol_config(propose_l1_block).await? else {
+            return Ok(());
+        };
+        let fork = Fork::of_block(batch.first_block_id()?, &config);
+        if fork != batch.fork {
+            tracing::warn!(
+                "Batch {} was proposed with {} events but its blocks are in the {} fork",
+                batch.batch_id,
+                batch.fork.as_str(),
+                fork.as_str()
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn index_batch_proposed(
+        &self,
+        tx: &mut RangeTx,
+        logs: &[Log],
+    ) -> Result<(u64, u64), Error> {
+        let mut propsed_batch_id = 0;
+        let mut proposed_block_id = 0;
+
+        // write batches in batch id order regardless of the log order
+        let mut batches = logs
+            .iter()
+            .filter_map(|log| {
+                let fork = fork::fork_of_log(log, Fork::proposed_signature)?;
+                Some(fork.decode_proposed(log).map(|batch| (batch, log)))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        batches.sort_by_key(|(batch, _)| batch.batch_id);
+
+        // a transaction can propose several batches, its fee is split between them
+        let mut propose_fees = BTreeMap::new();
+        for (_, log) in &batches {
+            let tx_hash = Self::get_log_tx_hash(log)?;
+            if let btree_map::Entry::Vacant(entry) = propose_fees.entry(tx_hash) {
+                let receipt = self.get_receipt(tx_hash).await?;
+                entry.insert(self.split_propose_fee(&receipt)?);
+            }
+        }
+
+        for (batch, log) in batches {
+            let tx_hash = Self::get_log_tx_hash(log)?;
+            let receipt = self.get_receipt(tx_hash).await?;
+            let (propose_fee_split, propose_fee) = propose_fees
+                .get(&tx_hash)
+                .and_then(|(split, fees)| Some((*split, *fees.get(&batch.batch_id)?)))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Proposal event of batch {} not found in transaction {}",
+                        batch.batch_id,
+                        tx_hash
+                    )
+                })?;
+            let propose_l1_block = self.store_log_block(tx, log).await?;
+            let propose_l1_timestamp = self.get_l1_timestamp(propose_l1_block).await?;
+            self.store_tx_cost(tx, &receipt).await?;
+
+            self.check_batch_fork(tx, &batch, propose_l1_block).await?;
+
+            propsed_batch_id = propsed_batch_id.max(batch.batch_id);
+            proposed_block_id = proposed_block_id.max(batch.last_block_id);
+            tx.insert_batch(
+                &batch,
+                &BatchProposal {
+                    tx_hash: tx_hash.to_string(),
+                    l1_block: propose_l1_block,
+                    l1_timestamp: propose_l1_timestamp,
+                    sender: receipt.from,
+                    fee: propose_fee,
+                    fee_split: propose_fee_split,
+                },
+            )
+            .await?;
+            self.store_l2_block_gas(tx, batch.first_block_id()?, batch.last_block_id)
+                .await?;
+        }
+
+        Ok((propsed_batch_id, proposed_block_id))
+    }
+
+    pub async fn index_batch_proved(
+        &self,
+        tx: &mut RangeTx,
+        logs: &[Log],
+    ) -> Result<(u64, u64), Error> {
+        let mut proved_batch_id = 0;
+        let mut proved_block_id = 0u64;
+        // a transaction can prove batches in several events, its fee is split
+        // between all of them
+        let mut prove_fees = BTreeMap::new();
+
+        for log in logs {
+            let Some(fork) = fork::fork_of_log(log, Fork::proved_signature) else {
+                continue;
+            };
+            let prove_l1_block = self.store_log_block(tx, log).await?;
+            let proved_at = self.get_l1_timestamp(prove_l1_block).await?;
+            let receipt = self.get_receipt(Self::get_log_tx_hash(log)?).await?;
+            self.store_tx_cost(tx, &receipt).await?;
+
+            let tx_hash = Self::get_log_tx_hash(log)?.to_string();
+            if let btree_map::Entry::Vacant(entry) = prove_fees.entry(tx_hash.clone()) {
+                entry.insert(self.split_prove_fee(tx, &receipt).await?);
+            }
+            let proofs =
+                Self::get_event_proofs(log, fork, proved_at, &receipt, &prove_fees[&tx_hash])?;
+            tracing::debug!("Proved {} batches", proofs.len());
+
+            for proof in proofs {
+                let batch_id: u64 = proof.batch_id.try_into()?;
+                if let Some(batch) = tx.get_batch_by_id(proof.batch_id).await? {
+                    proved_batch_id = proved_batch_id.max(batch_id);
+                    proved_block_id = proved_block_id.max(batch.last_block_id.try_into()?);
+                    self.apply_proof(tx, batch, &proof).await?;
+                } else {
+                    tracing::warn!(
+                        "Batch with id {} not found, proof {} is pending",
+                        batch_id,
+                        proof.prove_tx
+                    );
+                    tx.insert_pending_proof(&proof).await?;
+                }
+            }
+        }
+        Ok((proved_batch_id, proved_block_id))
+    }
+
+    /// Proofs of the batches proved by a proof event, each with its share of
+    /// the fee of the proof transaction
+    fn get_event_proofs(
+        log: &Log,
+        fork: Fork,
+        proved_at: u64,
+        receipt: &TransactionReceipt,
+        (prove_fee_split, prove_fees): &(ProveFeeSplit, BTreeMap<u64, u128>),
+    ) -> Result<Vec<PendingProof>, Error> {
+        let batches = fork.decode_proved(log)?;
+        let prove_l1_block = log
+            .block_number
+            .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Log block number not found")))?;
+        let tx_hash = Self::get_log_tx_hash(log)?.to_string();
+        let log_index = Self::get_log_index(log)?;
+
+        batches
+            .transitions
+            .iter()
+            .map(|transition| {
+                let batch_id = transition.batch_id;
+                let prove_fee = prove_fees.get(&batch_id).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Proof event of batch {} not found in transaction {}",
+                        batch_id,
+                        tx_hash
+                    )
+                })?;
+                Ok(PendingProof {
+                    batch_id: batch_id.try_into()?,
+                    prove_tx: tx_hash.clone(),
+                    l1_block: prove_l1_block.try_into()?,
+                    l1_timestamp: proved_at.try_into()?,
+                    log_index: log_index.try_into()?,
+                    verifier: batches.verifier.clone(),
+                    parent_hash: transition.parent_hash.to_string(),
+                    block_hash: transition.block_hash.to_string(),
+                    state_root: transition.state_root.to_string(),
+                    prove_fee: prove_fee.to_string(),
+                    prove_fee_split: Some(prove_fee_split.as_str().to_string()),
+                    prove_sender: receipt.from.to_string(),
+                    prover: transition.prover.map(|prover| prover.to_string()),
+                })
+            })
+            .collect()
+    }
+
+    /// Stores the proof in the batch_proof table and points the batch to its
+    /// effective proof
+    async fn apply_proof(
+        &self,
+        tx: &mut RangeTx,
+        mut batch: Batch,
+        proof: &PendingProof,
+    ) -> Result<(), Error> {
+        let prove_fee = proof.prove_fee.parse::<u128>()?;
+        let proved_at: u64 = proof.l1_timestamp.try_into()?;
+        let proposed_at: u64 = batch.proposed_at.try_into()?;
+        // only Pacaya proofs have their transition stored with the prover
+        let transition = match proof.prover {
+            Some(_) => None,
+            None => {
+                self.get_transition_state(
+                    batch.batch_id,
+                    proof.l1_block,
+                    &proof.parent_hash,
+                    &proof.block_hash,
+                    &proof.state_root,
+                )
+                .await?
+            }
+        };
+        let (prover, in_proving_window, prover_attribution) = match (transition, &proof.prover) {
+            (Some(transition), _) => (
+                transition.prover.to_string(),
+                Some(transition.inProvingWindow),
+                ProverAttribution::Transition,
+            ),
+            // Ontake proving windows depend on the proof tier, not on the
+            // Pacaya proving_window
+            (None, Some(prover)) => (prover.clone(), None, ProverAttribution::Event),
+            (None, None) => {
+                let in_proving_window = Self::is_in_proving_window(
+                    self.get_proving_window(tx, &batch).await?,
+                    proved_at,
+                    proposed_at,
+                );
+                (
+                    Self::get_prover(
+                        proof.prove_sender.as_str(),
+                        batch.sender.as_str(),
+                        in_proving_window,
+                    ),
+                    Some(in_proving_window),
+                    ProverAttribution::ProvingWindow,
+                )
+            }
+        };
+        let l2_fee_earned = match &batch.l2_fee_earned {
+            Some(l2_fee_earned) => l2_fee_earned.parse::<u128>()?,
+            None => {
+                let sharing_pctg = batch.base_fee_sharing_pctg.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Base fee sharing percentage of batch {} not found",
+                        batch.batch_id
+                    )
+                })?;
+                self.calculate_l2_fee_revenue(tx, &mut batch, sharing_pctg.try_into()?)
+                    .await?
+            }
+        };
+        let is_profitable = l2_fee_earned
+            > prove_fee
+                + batch
+                    .propose_fee
+                    .parse::<u128>()
+                    .context("Failed to parse propose fee")?;
+
+        tx.insert_batch_proof(BatchProof {
+            batch_id: batch.batch_id,
+            prove_tx: proof.prove_tx.clone(),
+            l1_block: proof.l1_block,
+            l1_timestamp: proof.l1_timestamp,
+            log_index: proof.log_index,
+            verifier: proof.verifier.clone(),
+            parent_hash: proof.parent_hash.clone(),
+            block_hash: proof.block_hash.clone(),
+            state_root: proof.state_root.clone(),
+            prove_fee: proof.prove_fee.clone(),
+            prove_fee_split: proof.prove_fee_split.clone(),
+            prove_sender: proof.prove_sender.clone(),
+            is_proved_by_proposer: prover == batch.proposer,
+            prover,
+            in_proving_window,
+            prover_attribution,
+            is_profitable,
+        })
+        .await?;
+
+        batch.l2_fee_earned = Some(l2_fee_earned.to_string());
+        tx.update_batch(batch).await
+    }
+
+    /// Applies pending proofs whose batches have been indexed since, and drops
+    /// those of batches proposed before L1_START_BLOCK
+    /// Stores the share of the fee of `prove_tx` of every batch it proved
+    /// that has the proof applied, and whether the batch is profitable with it
+    async fn update_prove_fees(
+        &self,
+        tx: &mut RangeTx,
+        prove_tx: &str,
+        split: ProveFeeSplit,
+        prove_fees: &BTreeMap<u64, u128>,
+    ) -> Result<(), Error> {
+        for (batch_id, prove_fee) in prove_fees {
+            let batch_id: i64 = (*batch_id).try_into()?;
+            tx.update_prove_fee(batch_id, prove_tx, *prove_fee, split)
+                .await?;
+            if let Some(batch) = tx.get_batch_by_id(batch_id).await?
+                && let Some(l2_fee_earned) = &batch.l2_fee_earned
+            {
+                tx.update_proof_profitability(
+                    batch_id,
+                    l2_fee_earned.parse()?,
+                    batch
+                        .propose_fee
+                        .parse::<u128>()
+                        .context("Failed to parse propose fee")?,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn apply_pending_proofs(&self, tx: &mut RangeTx) -> Result<(u64, u64), Error> {
+        let mut proved_batch_id = 0;
+        let mut proved_block_id = 0u64;
+
+        for proof in tx.get_resolvable_pending_proofs().await? {
+            if let Some(batch) = tx.get_batch_by_id(proof.batch_id).await? {
+                tracing::info!(
+                    "Applying pending proof {} for batch {}",
+                    proof.prove_tx,
+                    proof.batch_id
+                );
+                proved_batch_id = proved_batch_id.max(proof.batch_id.try_into()?);
+                proved_block_id = proved_block_id.max(batch.last_block_id.try_into()?);
+                self.apply_proof(tx, batch, &proof).await?;
+                tx.delete_pending_proof(proof.batch_id, &proof.prove_tx)
+                    .await?;
+
+                // the fee was split evenly while this batch was missing
+                let receipt = self.get_receipt(TxHash::from_str(&proof.prove_tx)?).await?;
+                let (split, prove_fees) = self.split_prove_fee(tx, &receipt).await?;
+                self.update_prove_fees(tx, &proof.prove_tx, split, &prove_fees)
+                    .await?;
+            }
+        }
+
+        let dropped = tx.delete_unresolvable_pending_proofs().await?;
+        if dropped > 0 {
+            tracing::info!(
+                "Dropped {} pending proofs of batches before the first indexed batch",
+                dropped
+            );
+        }
+
+        let pending = tx.get_pending_proof_count().await?;
+        if pending > 0 {
+            tracing::warn!("{} proofs are pending for unknown batches", pending);
+        }
+
+        Ok((proved_batch_id, proved_block_id))
+    }
+
+    pub async fn index_conflicting_proofs(
+        &self,
+        tx: &mut RangeTx,
+        logs: &[Log],
+    ) -> Result<(), Error> {
+        for log in logs {
+            let conflict = log.log_decode::<ITaikoInbox::ConflictingProof>()?;
+            let l1_block = self.store_log_block(tx, log).await?;
+            tracing::warn!(
+                "Conflicting proof for batch {} at L1 block {}",
+                conflict.inner.batchId,
+                l1_block
+            );
+
+            tx.insert_proof_conflict(
+                conflict,
+                Self::get_log_tx_hash(log)?.to_string(),
+                l1_block,
+                Self::get_log_index(log)?,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn index_bond_events(
+        &self,
+        tx: &mut RangeTx,
+        logs: &[Log],
+        balances: &BTreeMap<Address, U256>,
+        l1_block: u64,
+    ) -> Result<(), Error> {
+        for log in logs {
+            let Some((address, kind, amount)) = Self::decode_bond_event(log)? else {
+                continue;
+            };
+            let log_l1_block = self.store_log_block(tx, log).await?;
+            let l1_timestamp = self.get_l1_timestamp(log_l1_block).await?;
+            tx.insert_bond_event(BondEvent {
+                tx_hash: Self::get_log_tx_hash(log)?.to_string(),
+                log_index: Self::get_log_index(log)?,
+                l1_block: log_l1_block,
+                l1_timestamp,
+                address,
+                kind,
+                amount,
+            })
+            .await?;
+        }
+
+        self.reconcile_bond_balances(tx, balances, l1_block).await
+    }
+
+    /// Account, kind and amount of a bond event, None for any other event
+    fn decode_bond_event(log: &Log) -> Result<Option<(Address, BondEventKind, U256)>, Error> {
+        let topic = log.topic0().copied().unwrap_or_default();
+        let event = if topic == ITaikoInbox::BondDeposited::SIGNATURE_HASH {
+            let event = log.log_decode::<ITaikoInbox::BondDeposited>()?;
+            (
+                event.inner.user,
+                BondEventKind::Deposited,
+                event.inner.amount,
+            )
+        } else if topic == ITaikoInbox::BondWithdrawn::SIGNATURE_HASH {
+            let event = log.log_decode::<ITaikoInbox::BondWithdrawn>()?;
+            (
+                event.inner.user,
+                BondEventKind::Withdrawn,
+                event.inner.amount,
+            )
+        } else if topic == ITaikoInbox::BondCredited::SIGNATURE_HASH {
+            let event = log.log_decode::<ITaikoInbox::BondCredited>()?;
+            (
+                event.inner.user,
+                BondEventKind::Credited,
+                event.inner.amount,
+            )
+        } else if topic == ITaikoInbox::BondDebited::SIGNATURE_HASH {
+            let event = log.log_decode::<ITaikoInbox::BondDebited>()?;
+            (event.inner.user, BondEventKind::Debited, event.inner.amount)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(event))
+    }
+
+    /// Compares the indexed bond ledger with `bondBalanceOf` at `l1_block`.
+    /// The difference is the balance held before `L1_START_BLOCK` and must stay
+    /// constant, otherwise bond events are missing from the ledger. How far it
+    /// moved is stored as the drift of the reconciliation.
+    async fn reconcile_bond_balances(
+        &self,
+        tx: &mut RangeTx,
+        balances: &BTreeMap<Address, U256>,
+        l1_block: u64,
+    ) -> Result<(), Error> {
+        for (address, balance) in balances {
+            let ledger_balance = tx.get_bond_ledger_balance(*address).await?;
+            let offset = I256::try_from(*balance)? - ledger_balance;
+            let drift = match tx.get_bond_balance_offset(*address).await? {
+                Some(first_offset) => offset - first_offset,
+                None => I256::ZERO,
+            };
+
+            if !drift.is_zero() {
+                tracing::warn!(
+                    "Bond ledger of {} differs from bondBalanceOf by {} at L1 block {}",
+                    address,
+                    drift,
+                    l1_block
+                );
+            }
+
+            tx.update_bond_balance(*address, l1_block, *balance, ledger_balance, drift)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn index_batches_verified(
+        &self,
+        tx: &mut RangeTx,
+        logs: &[Log],
+    ) -> Result<u64, Error> {
+        let mut verified_batch_id = 0;
+
+        for log in logs {
+            let Some(fork) = fork::fork_of_log(log, Fork::verified_signature) else {
+                continue;
+            };
+            let verified = fork.decode_verified(log)?;
+            let verified_l1_block = self.store_log_block(tx, log).await?;
+            let verified_at = self.get_l1_timestamp(verified_l1_block).await?;
+
+            verified_batch_id = verified_batch_id.max(verified.batch_id);
+            tx.verify_batches(
+                verified.batch_id,
+                verified_l1_block,
+                verified_at,
+                verified.block_hash.to_string(),
+            )
+            .await?;
+        }
+
+        Ok(verified_batch_id)
+    }
+
+    /// Fetches logs and counts them towards the size of the current range
+    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, Error> {
+        let logs = if self.cross_check_logs {
+            self.get_cross_checked_logs(filter).await?
+        } else {
+            self.l1_provider
+                .get_logs(filter)
+                .await
+                .map_err(error::log_range_error)?
+        };
+        self.range_log_count
+            .fetch_add(logs.len(), Ordering::Relaxed);
+        Ok(logs)
+    }
+
+    /// Fetches logs from the two best ranked L1 endpoints and fails the range
+    /// if they disagree, so a provider silently dropping logs can't leave gaps
+    async fn get_cross_checked_logs(&self, filter: &Filter) -> Result<Vec<Log>, Error> {
+        let [primary, secondary] = <[FailoverTransport; 2]>::try_from(
+            self.l1_transport.ranked_endpoints(2),
+        )
+        .map_err(|_| IndexerError::Fatal(anyhow::anyhow!("Cross-check needs two L1 endpoints")))?;
+        let (primary_provider, secondary_provider) = (primary.provider(), secondary.provider());
+        let (primary_logs, secondary_logs) = tokio::try_join!(
+            primary_provider.get_logs(filter),
+            secondary_provider.get_logs(filter)
+        )
+        .map_err(error::log_range_error)?;
+
+        let log_ids = |logs: &[Log]| {
+            logs.iter()
+                .map(|log| (log.block_hash, log.transaction_hash, log.log_index))
+                .collect::<BTreeSet<_>>()
+        };
+        if log_ids(&primary_logs) != log_ids(&secondary_logs) {
+            let reason = format!(
+                "eth_getLogs mismatch: {} returned {} logs, {} returned {}",
+                primary.urls(),
+                primary_logs.len(),
+                secondary.urls(),
+                secondary_logs.len()
+            );
+            // The endpoint that returned fewer logs is most likely the one dropping them
+            if primary_logs.len() < secondary_logs.len() {
+                primary.penalize(&reason);
+            } else if secondary_logs.len() < primary_logs.len() {
+                secondary.penalize(&reason);
+            }
+            return Err(IndexerError::Rpc(anyhow::anyhow!(reason)).into());
+        }
+        Ok(primary_logs)
+    }
+
+    fn get_log_tx_hash(log: &Log) -> Result<TxHash, Error> {
+        Ok(log
+            .transaction_hash
+            .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Log transaction hash not found")))?)
+    }
+
+    fn get_log_index(log: &Log) -> Result<u64, Error> {
+        Ok(log
+            .log_index
+            .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Log index not found")))?)
+    }
+
+    async fn get_receipt(&self, tx_hash: TxHash) -> Result<TransactionReceipt, Error> {
+        if let Some(receipt) = self.cache.get_receipt(&tx_hash) {
+            return Ok(receipt);
+        }
+        let receipt = self
+            .l1_provider
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .ok_or_else(|| {
+                IndexerError::Rpc(anyhow::anyhow!(
+                    "Transaction receipt not found for {tx_hash}"
+                ))
+            })?;
+        self.cache.insert_receipt(receipt.clone());
+        Ok(receipt)
+    }
+
+    async fn get_cached_l1_header(&self, block_number: u64) -> Result<Header, Error> {
+        if let Some(header) = self.cache.get_header(block_number) {
+            return Ok(header);
+        }
+        let header = self.get_l1_header(block_number).await?;
+        self.cache.insert_header(header.clone());
+        Ok(header)
+    }
+
+    async fn get_l1_timestamp(&self, block_number: u64) -> Result<u64, Error> {
+        Ok(self.get_cached_l1_header(block_number).await?.timestamp)
+    }
+
+    /// Stores gas, fee and blob components of a proposal or proof transaction
+    async fn store_tx_cost(
+        &self,
+        tx: &mut RangeTx,
+        receipt: &TransactionReceipt,
+    ) -> Result<(), Error> {
+        let l1_block = receipt
+            .block_number
+            .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Receipt block number not found")))?;
+        let base_fee_per_gas = self
+            .get_cached_l1_header(l1_block)
+            .await?
+            .base_fee_per_gas
+            .map(u128::from)
+            .ok_or_else(|| {
+                IndexerError::Rpc(anyhow::anyhow!("Base fee of L1 block {l1_block} not found"))
+            })?;
+        let blob_gas_used = receipt.blob_gas_used.unwrap_or(0);
+
+        tx.insert_tx_cost(TxCost {
+            tx_hash: receipt.transaction_hash.to_string(),
+            l1_block: l1_block.try_into()?,
+            gas_used: receipt.gas_used.try_into()?,
+            effective_gas_price: receipt.effective_gas_price.to_string(),
+            base_fee_per_gas: base_fee_per_gas.to_string(),
+            priority_fee_per_gas: receipt
+                .effective_gas_price
+                .saturating_sub(base_fee_per_gas)
+                .to_string(),
+            blob_gas_used: blob_gas_used.try_into()?,
+            blob_gas_price: receipt.blob_gas_price.unwrap_or(0).to_string(),
+            blob_count: (blob_gas_used / DATA_GAS_PER_BLOB).try_into()?,
+        })
+        .await
+    }
+
+    /// Fetches receipts of all transactions with `logs` concurrently, using
+    /// eth_getBlockReceipts when enabled and supported by the provider
+    async fn prefetch_receipts(&self, logs: &[Log]) -> Result<(), Error> {
+        let mut tx_hashes = BTreeMap::new();
+        for log in logs {
+            let tx_hash = Self::get_log_tx_hash(log)?;
+            if !self.cache.has_receipt(&tx_hash) {
+                tx_hashes.insert(tx_hash, log.block_number);
+            }
+        }
+        if tx_hashes.is_empty() {
+            return Ok(());
+        }
+
+        if self.use_block_receipts.load(Ordering::Relaxed) {
+            let blocks: BTreeSet<u64> = tx_hashes.values().flatten().copied().collect();
+            let result = stream::iter(blocks)
+                .map(|block_number| async move {
+                    self.l1_provider
+                        .get_block_receipts(block_number.into())
+                        .await
+                })
+                .buffer_unordered(self.rpc_concurrency)
+                .try_collect::<Vec<_>>()
+                .await;
+            match result {
+                Ok(blocks) => {
+                    blocks
+                        .into_iter()
+                        .flatten()
+                        .flatten()
+                        .filter(|receipt| tx_hashes.contains_key(&receipt.transaction_hash))
+                        .for_each(|receipt| self.cache.insert_receipt(receipt));
+                }
+                Err(TransportError::ErrorResp(e)) if e.code == -32601 || e.code == -32600 => {
+                    tracing::warn!(
+                        "eth_getBlockReceipts is not supported by the provider ({}), falling back to eth_getTransactionReceipt",
+                        e.message
+                    );
+                    self.use_block_receipts.store(false, Ordering::Relaxed);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        stream::iter(tx_hashes.into_keys())
+            .map(|tx_hash| self.get_receipt(tx_hash))
+            .buffer_unordered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fetches headers of all blocks with `logs` concurrently
+    async fn prefetch_headers(&self, logs: &[Log]) -> Result<(), Error> {
+        let blocks: BTreeSet<u64> = logs
+            .iter()
+            .filter_map(|log| log.block_number)
+            .filter(|block_number| !self.cache.has_header(*block_number))
+            .collect();
+
+        stream::iter(blocks)
+            .map(|block_number| self.get_l1_timestamp(block_number))
+            .buffer_unordered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Stores the hash of the block containing `log` and returns the block number
+    async fn store_log_block(&self, tx: &mut RangeTx, log: &Log) -> Result<u64, Error> {
+        let block_number = log
+            .block_number
+            .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Log block number not found")))?;
+        let block_hash = log
+            .block_hash
+            .ok_or_else(|| IndexerError::Rpc(anyhow::anyhow!("Log block hash not found")))?;
+        tx.insert_l1_block(block_number, block_hash.to_string())
+            .await?;
+        Ok(block_number)
+    }
+
+    /// Reads the fees paid in the L2 blocks of a batch, stores gas used and
+    /// base fee of each block and sets the priority fees and the coinbase share
+    /// of the base fee on the batch. The rest of the base fee goes to the
+    /// treasury. Returns the total L2 revenue of the batch.
+    async fn calculate_l2_fee_revenue(
+        &self,
+        tx: &mut RangeTx,
+        batch: &mut Batch,
+        sharing_pctg: u8,
+    ) -> Result<u128, Error> {
+        let last_block_number: u64 = batch.last_block_id.try_into()?;
+        let block_count: u64 = batch.block_count.try_into()?;
+        let first_block_number = (last_block_number + 1).saturating_sub(block_count);
+
+        let blocks = stream::iter(first_block_number..=last_block_number)
+            .map(|block_number| self.get_l2_block_fees(block_number, sharing_pctg))
+            .buffer_unordered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut priority_fee = 0;
+        let mut base_fee_share = 0;
+        for block in blocks {
+            tx.update_l2_block_gas(block.block_number, block.gas_used, block.base_fee)
+                .await?;
+            priority_fee += block.priority_fee;
+            base_fee_share += block.base_fee_share;
+        }
+        batch.l2_priority_fee = Some(priority_fee.to_string());
+        batch.l2_base_fee_share = Some(base_fee_share.to_string());
+
+        Ok(priority_fee + base_fee_share)
+    }
+
+    /// Stores gas used and base fee of the L2 blocks of a batch
+    async fn store_l2_block_gas(
+        &self,
+        tx: &mut RangeTx,
+        first_block_id: u64,
+        last_block_id: u64,
+    ) -> Result<(), Error> {
+        let headers = stream::iter(first_block_id..=last_block_id)
+            .map(|block_number| self.get_l2_header(block_number))
+            .buffered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+        for header in headers {
+            let base_fee = header.base_fee_per_gas.ok_or_else(|| {
+                IndexerError::Rpc(anyhow::anyhow!(
+                    "Base fee of L2 block {} not found",
+                    header.number
+                ))
+            })?;
+            tx.update_l2_block_gas(header.number, header.gas_used, base_fee)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn get_l2_header(&self, block_number: u64) -> Result<Header, Error> {
+        if let Some(header) = self.cache.get_l2_header(block_number) {
+            return Ok(header);
+        }
+        if self.cache.is_sealed() {
+            return Err(IndexerError::Fatal(anyhow::anyhow!(
+                "L2 block {block_number} was not fetched with the range"
+            ))
+            .into());
+        }
+
+        let header = self
+            .l2_provider
+            .get_block_by_number(block_number.into())
+            .await?
+            .map(|block| block.header)
+            .ok_or_else(|| {
+                IndexerError::Rpc(anyhow::anyhow!("L2 block {block_number} not found"))
+            })?;
+        self.cache.insert_l2_header(header.clone());
+
+        Ok(header)
+    }
+
+    /// L2 block with the receipts of its transactions
+    async fn get_l2_block(&self, block_number: u64) -> Result<L2Block, Error> {
+        if let Some(block) = self.cache.get_l2_block(block_number) {
+            return Ok(block);
+        }
+        if self.cache.is_sealed() {
+            return Err(IndexerError::Fatal(anyhow::anyhow!(
+                "L2 block {block_number} was not fetched with the range"
+            ))
+            .into());
+        }
+
+        let (block, receipts) = tokio::try_join!(
+            self.l2_provider.get_block_by_number(block_number.into()),
+            self.l2_provider.get_block_receipts(block_number.into()),
+        )?;
+        let header = block.map(|block| block.header).ok_or_else(|| {
+            IndexerError::Rpc(anyhow::anyhow!("L2 block {block_number} not found"))
+        })?;
+        let receipts = receipts.ok_or_else(|| {
+            IndexerError::Rpc(anyhow::anyhow!(
+                "Receipts of L2 block {block_number} not found"
+            ))
+        })?;
+        let block = L2Block { header, receipts };
+        self.cache.insert_l2_block(block.clone());
+
+        Ok(block)
+    }
+
+    async fn get_l2_block_fees(
+        &self,
+        block_number: u64,
+        sharing_pctg: u8,
+    ) -> Result<L2BlockFees, Error> {
+        let L2Block { header, receipts } = self.get_l2_block(block_number).await?;
+        let base_fee = header.base_fee_per_gas.ok_or_else(|| {
+            IndexerError::Rpc(anyhow::anyhow!(
+                "Base fee of L2 block {block_number} not found"
+            ))
+        })?;
+
+        let mut fees = L2BlockFees {
+            block_number,
+            gas_used: header.gas_used,
+            base_fee,
+            priority_fee: 0,
+            base_fee_share: 0,
+        };
+        let base_fee = u128::from(base_fee);
+        for receipt in receipts
+            .iter()
+            .filter(|receipt| receipt.from != GOLDEN_TOUCH_ADDRESS)
+        {
+            let gas_used = u128::from(receipt.gas_used);
+            fees.priority_fee += gas_used * receipt.effective_gas_price.saturating_sub(base_fee);
+            // shared per transaction like the L2 client does, so rounding matches
+            fees.base_fee_share += gas_used * base_fee * u128::from(sharing_pctg) / 100;
+        }
+
+        Ok(fees)
+    }
+
+    /// Transition stored by the inbox for a proof, read at the L1 block of the
+    /// proof. None when the call reverts, e.g. on a node without historical
+    /// state, or when another proof replaced the transition in the same block.
+    async fn get_transition_state(
+        &self,
+        batch_id: i64,
+        l1_block: i64,
+        parent_hash: &str,
+        block_hash: &str,
+        state_root: &str,
+    ) -> Result<Option<ITaikoInbox::TransitionState>, Error> {
+        let transition = self
+            .get_cached_transition((
+                batch_id.try_into()?,
+                l1_block.try_into()?,
+                B256::from_str(parent_hash)?,
+            ))
+            .await?;
+
+        Ok(transition.filter(|transition| {
+            transition.blockHash.to_string() == block_hash
+                && transition.stateRoot.to_string() == state_root
+        }))
+    }
+
+    async fn get_cached_transition(
+        &self,
+        key: TransitionKey,
+    ) -> Result<Option<ITaikoInbox::TransitionState>, Error> {
+        if let Some(transition) = self.cache.get_transition(&key) {
+            return Ok(transition);
+        }
+        let (batch_id, l1_block, parent_hash) = key;
+        if self.cache.is_sealed() {
+            return Err(IndexerError::Fatal(anyhow::anyhow!(
+                "Transition of batch {batch_id} at L1 block {l1_block} was not fetched with the range"
+            ))
+            .into());
+        }
+
+        let ti_contract = ITaikoInbox::new(self.taiko_inbox, &self.l1_provider);
+        let result = ti_contract
+            .getTransitionByParentHash(batch_id, parent_hash)
+            .block(l1_block.into())
+            .call()
+            .await;
+        let transition = match result {
+            Ok(transition) => Some(transition),
+            Err(alloy::contract::Error::TransportError(TransportError::ErrorResp(e)))
+                if !e.is_retry_err() =>
+            {
+                tracing::debug!(
+                    "Transition of batch {} not readable at L1 block {}: {}",
+                    batch_id,
+                    l1_block,
+                    e.message
+                );
+                None
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.cache.insert_transition(key, transition.clone());
+
+        Ok(transition)
+    }
+
+    /// Reads pacayaConfig at `block`. None when the call fails, e.g. on a node
+    /// without historical state.
+    async fn read_protocol_config(&self, block: BlockId) -> Result<Option<ProtocolConfig>, Error> {
+        let ti_contract = ITaikoInbox::new(self.taiko_inbox, &self.l1_provider);
+        let config = match ti_contract.pacayaConfig().block(block).call().await {
+            Ok(config) => config,
+            Err(alloy::contract::Error::TransportError(TransportError::ErrorResp(e)))
+                if !e.is_retry_err() =>
+            {
+                tracing::debug!(
+                    "pacayaConfig not readable at L1 block {block}: {}",
+                    e.message
+                );
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Some(ProtocolConfig::try_from(&config)?))
+    }
+
+    /// Proving window of the protocol config in force when the batch was
+    /// proposed
+    async fn get_proving_window(&self, tx: &mut RangeTx, batch: &Batch) -> Result<u64, Error> {
+        let config = tx
+            .get_protocol_config(batch.propose_l1_block.try_into()?)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No protocol config recorded"))?;
+        Ok(config.proving_window.try_into()?)
+    }
+
+    /// Guesses the prover from the proof time when the transition stored by
+    /// the inbox is not available
+    fn get_prover(prove_sender: &str, propose_sender: &str, in_proving_window: bool) -> String {
+        if in_proving_window {
+            propose_sender.to_string()
+        } else {
+            prove_sender.to_string()
+        }
+    }
+
+    fn is_in_proving_window(proving_window: u64, proved_at: u64, proposed_at: u64) -> bool {
+        proved_at <= proposed_at + proving_window
+    }
+
+    /// Splits the fee of a proposal transaction between the batches proposed
+    /// in its proposal events, keyed by batch id
+    fn split_propose_fee(
+        &self,
+        receipt: &TransactionReceipt,
+    ) -> Result<(ProposeFeeSplit, BTreeMap<u64, u128>), Error> {
+        let mut batches = receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| log.address() == self.taiko_inbox)
+            .filter_map(|log| {
+                fork::fork_of_log(log, Fork::proposed_signature)
+                    .map(|fork| fork.decode_proposed(log))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        batches.sort_by_key(|batch| batch.batch_id);
+
+        let weights = batches
+            .iter()
+            .map(|batch| {
+                Ok(match self.propose_fee_split {
+                    ProposeFeeSplit::Even => 1,
+                    ProposeFeeSplit::BlobBytes => u64::from(batch.blob_byte_size),
+                    ProposeFeeSplit::BlockCount => u64::try_from(batch.blocks.len())?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let (split, fees) = fee_split::split_fee(
+            self.propose_fee_split,
+            Self::get_tx_eth_price(receipt),
+            &weights,
+        );
+
+        Ok((
+            split,
+            batches
+                .iter()
+                .map(|batch| batch.batch_id)
+                .zip(fees)
+                .collect(),
+        ))
+    }
+
+    /// Splits the fee of a proof transaction between the batches proved in
+    /// its proof events, keyed by batch id. The split is even while
+    /// any of the batches is not indexed yet.
+    /// Batches proved by the inbox events of a proof transaction
+    fn get_proved_batch_ids(&self, receipt: &TransactionReceipt) -> Result<BTreeSet<u64>, Error> {
+        let mut batch_ids = BTreeSet::new();
+        for log in receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| log.address() == self.taiko_inbox)
+        {
+            if let Some(fork) = fork::fork_of_log(log, Fork::proved_signature) {
+                batch_ids.extend(
+                    fork.decode_proved(log)?
+                        .transitions
+                        .iter()
+                        .map(|transition| transition.batch_id),
+                );
+            }
+        }
+
+        Ok(batch_ids)
+    }
+
+    async fn split_prove_fee(
+        &self,
+        tx: &mut RangeTx,
+        receipt: &TransactionReceipt,
+    ) -> Result<(ProveFeeSplit, BTreeMap<u64, u128>), Error> {
+        let batch_ids = self.get_proved_batch_ids(receipt)?;
+
+        let mut batches = Vec::new();
+        for batch_id in &batch_ids {
+            batches.push(tx.get_batch_by_id((*batch_id).try_into()?).await?);
+        }
+        let (split, weights) = match batches.into_iter().collect::<Option<Vec<_>>>() {
+            Some(batches) => {
+                let mut weights = Vec::new();
+                for batch in &batches {
+                    weights.push(match self.prove_fee_split {
+                        ProveFeeSplit::Even => 1,
+                        ProveFeeSplit::BlockCount => batch.block_count.try_into()?,
+                        ProveFeeSplit::L2Gas => self.get_l2_gas_used(tx, batch).await?,
+                    });
+                }
+                (self.prove_fee_split, weights)
+            }
+            None => (ProveFeeSplit::Even, vec![1; batch_ids.len()]),
+        };
+        let (split, fees) = fee_split::split_fee(split, Self::get_tx_eth_price(receipt), &weights);
+
+        Ok((split, batch_ids.into_iter().zip(fees).collect()))
+    }
+
+    /// L2 gas used by a batch, read from its L2 blocks when it is not stored
+    /// yet
+    async fn get_l2_gas_used(&self, tx: &mut RangeTx, batch: &Batch) -> Result<u64, Error> {
+        if let Some(gas_used) = tx.get_l2_gas_used(batch.batch_id).await? {
+            return Ok(gas_used);
+        }
+
+        let last_block_number: u64 = batch.last_block_id.try_into()?;
+        let block_count: u64 = batch.block_count.try_into()?;
+        let first_block_number = (last_block_number + 1).saturating_sub(block_count);
+        stream::iter(first_block_number..=last_block_number)
+            .map(|block_number| self.get_l2_block(block_number))
+            .buffer_unordered(self.rpc_concurrency)
+            .try_fold(0, |total, block| async move {
+                Ok(total + block.header.gas_used)
+            })
+            .await
     }
 
-    fn get_tx_eth_price(receipt: &alloy::rpc::types::TransactionReceipt) -> u128 {
+    fn get_tx_eth_price(receipt: &TransactionReceipt) -> u128 {
         let gas_used = u128::from(receipt.gas_used);
         let gas_price = receipt.effective_gas_price;
         let blob_gas_used = u128::from(receipt.blob_gas_used.unwrap_or(0));
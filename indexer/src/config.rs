@@ -1,12 +1,27 @@
+use alloy::transports::http::reqwest::Url;
+
+use crate::fee_split::{FeeSplit, ProposeFeeSplit, ProveFeeSplit};
+
 pub struct Config {
-    pub db_filename: String,
+    pub database_url: String,
     pub l1_rpc_url: String,
     pub l2_rpc_url: String,
+    pub l1_ws_url: Option<String>,
     pub taiko_inbox_address: String,
     pub l1_start_block: u64,
     pub indexing_step: u64,
+    pub min_indexing_step: u64,
+    pub max_indexing_step: u64,
     pub sleep_duration_sec: u64,
     pub max_l1_fork_depth: u64,
+    pub retry_max_delay_sec: u64,
+    pub rpc_concurrency: usize,
+    pub use_block_receipts: bool,
+    pub cross_check_logs: bool,
+    pub propose_fee_split: ProposeFeeSplit,
+    pub prove_fee_split: ProveFeeSplit,
+    pub verifier_registry: String,
+    pub protocol_config_refresh_blocks: u64,
 }
 
 impl Config {
@@ -14,9 +29,17 @@ impl Config {
         // Load environment variables from .env file
         dotenvy::dotenv().ok();
 
-        let db_filename = std::env::var("DB_FILENAME").unwrap_or_else(|_| {
-            panic!("DB_FILENAME env var not found");
-        });
+        // DATABASE_URL selects the storage backend, DB_FILENAME is a shortcut
+        // for a SQLite database file
+        let database_url = std::env::var("DATABASE_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| {
+                let db_filename = std::env::var("DB_FILENAME").unwrap_or_else(|_| {
+                    panic!("DB_FILENAME or DATABASE_URL env var not found");
+                });
+                format!("sqlite://{db_filename}?mode=rwc")
+            });
 
         let l1_rpc_url = std::env::var("L1_RPC_URL").unwrap_or_else(|_| {
             panic!("L1_RPC_URL env var not found");
@@ -26,6 +49,10 @@ impl Config {
             panic!("L2_RPC_URL env var not found");
         });
 
+        let l1_ws_url = std::env::var("L1_WS_URL")
+            .ok()
+            .filter(|url| !url.is_empty());
+
         let taiko_inbox_address = std::env::var("TAIKO_INBOX_ADDRESS").unwrap_or_else(|_| {
             panic!("TAIKO_INBOX_ADDRESS env var not found");
         });
@@ -49,6 +76,26 @@ impl Config {
                 }
             })
             .expect("INDEXING_STEP must be a number");
+        let min_indexing_step = std::env::var("MIN_INDEXING_STEP")
+            .unwrap_or("1".to_string())
+            .parse::<u64>()
+            .inspect(|&val| {
+                if val == 0 {
+                    panic!("MIN_INDEXING_STEP must be a positive number");
+                }
+            })
+            .expect("MIN_INDEXING_STEP must be a number");
+
+        let max_indexing_step = std::env::var("MAX_INDEXING_STEP")
+            .unwrap_or("1000".to_string())
+            .parse::<u64>()
+            .inspect(|&val| {
+                if val < min_indexing_step {
+                    panic!("MAX_INDEXING_STEP must not be less than MIN_INDEXING_STEP");
+                }
+            })
+            .expect("MAX_INDEXING_STEP must be a number");
+
         let sleep_duration_sec = std::env::var("SLEEP_DURATION_SEC")
             .unwrap_or("12".to_string())
             .parse::<u64>()
@@ -69,27 +116,116 @@ impl Config {
             })
             .expect("MAX_L1_FORK_DEPTH must be a number");
 
+        let retry_max_delay_sec = std::env::var("RETRY_MAX_DELAY_SEC")
+            .unwrap_or("300".to_string())
+            .parse::<u64>()
+            .inspect(|&val| {
+                if val == 0 {
+                    panic!("RETRY_MAX_DELAY_SEC must be a positive number");
+                }
+            })
+            .expect("RETRY_MAX_DELAY_SEC must be a number");
+
+        let rpc_concurrency = std::env::var("RPC_CONCURRENCY")
+            .unwrap_or("8".to_string())
+            .parse::<usize>()
+            .inspect(|&val| {
+                if val == 0 {
+                    panic!("RPC_CONCURRENCY must be a positive number");
+                }
+            })
+            .expect("RPC_CONCURRENCY must be a number");
+
+        let use_block_receipts = std::env::var("USE_BLOCK_RECEIPTS")
+            .unwrap_or("false".to_string())
+            .parse::<bool>()
+            .expect("USE_BLOCK_RECEIPTS must be true or false");
+
+        let cross_check_logs = std::env::var("CROSS_CHECK_LOGS")
+            .unwrap_or("false".to_string())
+            .parse::<bool>()
+            .expect("CROSS_CHECK_LOGS must be true or false");
+
+        let propose_fee_split = std::env::var("PROPOSE_FEE_SPLIT")
+            .unwrap_or("even".to_string())
+            .parse::<ProposeFeeSplit>()
+            .expect("PROPOSE_FEE_SPLIT must be even, blob_bytes or block_count");
+
+        let prove_fee_split = std::env::var("PROVE_FEE_SPLIT")
+            .unwrap_or("even".to_string())
+            .parse::<ProveFeeSplit>()
+            .expect("PROVE_FEE_SPLIT must be even, block_count or l2_gas");
+
+        let verifier_registry = std::env::var("VERIFIER_REGISTRY").unwrap_or_default();
+
+        let protocol_config_refresh_blocks = std::env::var("PROTOCOL_CONFIG_REFRESH_BLOCKS")
+            .unwrap_or("300".to_string())
+            .parse::<u64>()
+            .inspect(|&val| {
+                if val == 0 {
+                    panic!("PROTOCOL_CONFIG_REFRESH_BLOCKS must be a positive number");
+                }
+            })
+            .expect("PROTOCOL_CONFIG_REFRESH_BLOCKS must be a number");
+
         tracing::info!(
-            "Config:\nDB_FILENAME: {}\nL1_RPC_URL: {}\nL2_RPC_URL: {}\nTAIKO_INBOX_ADDRESS: {}\nL1_START_BLOCK: {}\nINDEXING_STEP: {}\nSLEEP_DURATION_SEC: {}\nMAX_L1_FORK_DEPTH: {}",
-            db_filename,
+            "Config:\nDATABASE_URL: {}\nL1_RPC_URL: {}\nL2_RPC_URL: {}\nL1_WS_URL: {}\nTAIKO_INBOX_ADDRESS: {}\nL1_START_BLOCK: {}\nINDEXING_STEP: {}\nMIN_INDEXING_STEP: {}\nMAX_INDEXING_STEP: {}\nSLEEP_DURATION_SEC: {}\nMAX_L1_FORK_DEPTH: {}\nRETRY_MAX_DELAY_SEC: {}\nRPC_CONCURRENCY: {}\nUSE_BLOCK_RECEIPTS: {}\nCROSS_CHECK_LOGS: {}\nPROPOSE_FEE_SPLIT: {}\nPROVE_FEE_SPLIT: {}\nVERIFIER_REGISTRY: {}\nPROTOCOL_CONFIG_REFRESH_BLOCKS: {}",
+            redact_password(&database_url),
             l1_rpc_url,
             l2_rpc_url,
+            l1_ws_url.as_deref().unwrap_or("-"),
             taiko_inbox_address,
             l1_start_block,
             indexing_step,
+            min_indexing_step,
+            max_indexing_step,
             sleep_duration_sec,
-            max_l1_fork_depth
+            max_l1_fork_depth,
+            retry_max_delay_sec,
+            rpc_concurrency,
+            use_block_receipts,
+            cross_check_logs,
+            propose_fee_split.as_str(),
+            prove_fee_split.as_str(),
+            if verifier_registry.is_empty() {
+                "-"
+            } else {
+                verifier_registry.as_str()
+            },
+            protocol_config_refresh_blocks
         );
 
         Config {
-            db_filename,
+            database_url,
             l1_rpc_url,
             l2_rpc_url,
+            l1_ws_url,
             taiko_inbox_address,
             l1_start_block,
             indexing_step,
+            min_indexing_step,
+            max_indexing_step,
             sleep_duration_sec,
             max_l1_fork_depth,
+            retry_max_delay_sec,
+            rpc_concurrency,
+            use_block_receipts,
+            cross_check_logs,
+            propose_fee_split,
+            prove_fee_split,
+            verifier_registry,
+            protocol_config_refresh_blocks,
+        }
+    }
+}
+
+/// Hides the password of a database URL before it is logged
+fn redact_password(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut url) if url.password().is_some() => {
+            let _ = url.set_password(Some("***"));
+            url.to_string()
         }
+        _ => url.to_string(),
     }
 }
@@ -1,11 +1,24 @@
-use alloy::{primitives::Address, rpc::types::Log};
+use std::str::FromStr;
+
+use alloy::{
+    primitives::{Address, I256, U256},
+    rpc::types::Log,
+};
 use anyhow::Error;
+use batch_tracker_common::SqlBool;
 use sqlx::{
-    SqlitePool,
-    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
+    Any, AnyPool, Executor, Transaction,
+    any::{AnyPoolOptions, install_default_drivers},
 };
 
-use crate::taiko_inbox_binding::ITaikoInbox;
+use crate::{
+    error::IndexerError,
+    fee_split::{FeeSplit, ProposeFeeSplit, ProveFeeSplit},
+    fork::ProposedBatch,
+    migrations,
+    taiko_inbox_binding::ITaikoInbox,
+    verifier_registry::VerifierEntry,
+};
 
 #[allow(dead_code)]
 #[derive(sqlx::FromRow)]
@@ -15,150 +28,609 @@ pub struct Batch {
     pub proposer: String,
     pub coinbase: String,
     pub propose_tx: String,
+    pub propose_l1_block: i64,
     pub proposed_at: i64,
     pub last_block_id: i64,
     pub block_count: i64,
     pub propose_fee: String,
     pub l2_fee_earned: Option<String>,
-    pub prover: Option<String>,
     pub prove_tx: Option<String>,
-    pub prove_fee: Option<String>,
+    #[sqlx(try_from = "SqlBool")]
     pub is_sent_by_proposer: bool,
-    pub is_profitable: Option<bool>,
-    pub is_proved_by_proposer: Option<bool>,
+    pub verified_l1_block: Option<i64>,
+    pub verified_at: Option<i64>,
+    pub verified_block_hash: Option<String>,
+    pub base_fee_sharing_pctg: Option<i64>,
+    pub l2_priority_fee: Option<String>,
+    pub l2_base_fee_share: Option<String>,
+    pub propose_fee_split: Option<String>,
+    pub propose_l1_timestamp: Option<i64>,
+    pub fork: String,
+}
+
+#[derive(Clone, Copy)]
+pub enum BondEventKind {
+    Deposited,
+    Withdrawn,
+    Credited,
+    Debited,
+}
+
+impl BondEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BondEventKind::Deposited => "deposited",
+            BondEventKind::Withdrawn => "withdrawn",
+            BondEventKind::Credited => "credited",
+            BondEventKind::Debited => "debited",
+        }
+    }
+}
+
+impl FromStr for BondEventKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deposited" => Ok(BondEventKind::Deposited),
+            "withdrawn" => Ok(BondEventKind::Withdrawn),
+            "credited" => Ok(BondEventKind::Credited),
+            "debited" => Ok(BondEventKind::Debited),
+            _ => Err(anyhow::anyhow!("Unknown bond event kind {s}")),
+        }
+    }
+}
+
+/// Bond deposit, withdrawal, credit or debit of an address
+pub struct BondEvent {
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub l1_block: u64,
+    pub l1_timestamp: u64,
+    pub address: Address,
+    pub kind: BondEventKind,
+    pub amount: U256,
+}
+
+/// How the prover of a proof was determined
+#[derive(Clone, Copy)]
+pub enum ProverAttribution {
+    /// Read from the transition stored by the inbox
+    Transition,
+    /// Guessed from the proof time and the proving window
+    ProvingWindow,
+    /// Named by the proof event
+    Event,
+}
+
+impl ProverAttribution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProverAttribution::Transition => "transition",
+            ProverAttribution::ProvingWindow => "proving_window",
+            ProverAttribution::Event => "event",
+        }
+    }
+}
+
+pub struct BatchProof {
+    pub batch_id: i64,
+    pub prove_tx: String,
+    pub l1_block: i64,
+    pub l1_timestamp: i64,
+    pub log_index: i64,
+    pub verifier: String,
+    pub parent_hash: String,
+    pub block_hash: String,
+    pub state_root: String,
+    pub prove_fee: String,
+    pub prove_fee_split: Option<String>,
+    pub prove_sender: String,
+    pub prover: String,
+    /// None for Ontake proofs, whose proving window depends on their tier
+    pub in_proving_window: Option<bool>,
+    pub prover_attribution: ProverAttribution,
+    pub is_proved_by_proposer: bool,
+    pub is_profitable: bool,
+}
+
+/// Proposal transaction of a batch with the share of its fee charged to the
+/// batch
+pub struct BatchProposal {
+    pub tx_hash: String,
+    pub l1_block: u64,
+    pub l1_timestamp: u64,
+    pub sender: Address,
+    pub fee: u128,
+    pub fee_split: ProposeFeeSplit,
+}
+
+/// Cost components of a proposal or proof transaction, shared by all batches
+/// it proposed or proved
+pub struct TxCost {
+    pub tx_hash: String,
+    pub l1_block: i64,
+    pub gas_used: i64,
+    pub effective_gas_price: String,
+    pub base_fee_per_gas: String,
+    pub priority_fee_per_gas: String,
+    pub blob_gas_used: i64,
+    pub blob_gas_price: String,
+    pub blob_count: i64,
+}
+
+/// Proof of a single batch from a BatchesProved event. Kept in the
+/// pending_proof table while the batch itself is not indexed yet.
+#[derive(sqlx::FromRow)]
+pub struct PendingProof {
+    pub batch_id: i64,
+    pub prove_tx: String,
+    pub l1_block: i64,
+    pub l1_timestamp: i64,
+    pub log_index: i64,
+    pub verifier: String,
+    pub parent_hash: String,
+    pub block_hash: String,
+    pub state_root: String,
+    pub prove_fee: String,
+    /// Always even, as the batch was not indexed when the fee was split. The
+    /// fee is split again when the proof is applied.
+    pub prove_fee_split: Option<String>,
+    pub prove_sender: String,
+    /// Prover named by the proof event, only Ontake proofs carry it
+    pub prover: Option<String>,
+}
+
+/// Inbox configuration fields the indexer keeps a history of, as returned by
+/// pacayaConfig
+#[derive(Clone, Debug, PartialEq, Eq, sqlx::FromRow)]
+pub struct ProtocolConfig {
+    pub proving_window: i64,
+    pub cooldown_window: i64,
+    pub liveness_bond_base: String,
+    pub liveness_bond_per_block: String,
+    pub max_blocks_per_batch: i64,
+    pub ontake_fork_height: i64,
+    pub pacaya_fork_height: i64,
+    pub shasta_fork_height: i64,
+    pub unzen_fork_height: i64,
+}
+
+impl TryFrom<&ITaikoInbox::Config> for ProtocolConfig {
+    type Error = Error;
+
+    fn try_from(config: &ITaikoInbox::Config) -> Result<Self, Self::Error> {
+        Ok(Self {
+            proving_window: config.provingWindow.into(),
+            cooldown_window: config.cooldownWindow.to::<i64>(),
+            liveness_bond_base: config.livenessBondBase.to_string(),
+            liveness_bond_per_block: config.livenessBondPerBlock.to_string(),
+            max_blocks_per_batch: config.maxBlocksPerBatch.into(),
+            ontake_fork_height: config.forkHeights.ontake.try_into()?,
+            pacaya_fork_height: config.forkHeights.pacaya.try_into()?,
+            shasta_fork_height: config.forkHeights.shasta.try_into()?,
+            unzen_fork_height: config.forkHeights.unzen.try_into()?,
+        })
+    }
+}
+
+/// Effective proof of a batch: the latest proof of the verified transition,
+/// or the latest proof while the verified block hash of the batch is not known
+const EFFECTIVE_PROVE_TX: &str = r#"
+    COALESCE(
+        (
+            SELECT prove_tx FROM batch_proof
+            WHERE batch_proof.batch_id = batch.batch_id
+                AND batch_proof.block_hash = batch.verified_block_hash
+            ORDER BY l1_block DESC, log_index DESC
+            LIMIT 1
+        ),
+        (
+            SELECT prove_tx FROM batch_proof
+            WHERE batch_proof.batch_id = batch.batch_id
+            ORDER BY l1_block DESC, log_index DESC
+            LIMIT 1
+        )
+    )
+"#;
+
+/// Storage backend, selected by the scheme of the database URL. Queries are
+/// written once for both, so only connection setup depends on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    pub fn from_url(database_url: &str) -> Result<Self, Error> {
+        if database_url.starts_with("sqlite:") {
+            Ok(Backend::Sqlite)
+        } else if database_url.starts_with("postgres:") || database_url.starts_with("postgresql:") {
+            Ok(Backend::Postgres)
+        } else {
+            Err(anyhow::anyhow!(
+                "Unsupported database URL, expected sqlite: or postgres: scheme"
+            ))
+        }
+    }
 }
 
 pub struct DataBase {
-    pool: SqlitePool,
+    pool: AnyPool,
 }
 
 impl DataBase {
-    pub async fn new(db_filename: &str) -> Result<Self, Error> {
-        let options = SqliteConnectOptions::new()
-            .journal_mode(SqliteJournalMode::Wal)
-            .synchronous(SqliteSynchronous::Normal)
-            .filename(db_filename)
-            .create_if_missing(true);
-
-        let pool = SqlitePoolOptions::new()
+    pub async fn new(database_url: &str) -> Result<Self, Error> {
+        install_default_drivers();
+        let backend = Backend::from_url(database_url)?;
+
+        let pool = AnyPoolOptions::new()
             .max_connections(1)
-            .connect_with(options)
+            .after_connect(move |conn, _| {
+                Box::pin(async move {
+                    if backend == Backend::Sqlite {
+                        conn.execute("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+                            .await?;
+                    }
+                    Ok(())
+                })
+            })
+            .connect(database_url)
             .await?;
 
-        // Create batch table
-        sqlx::query(
+        migrations::run(&pool, backend).await?;
+
+        Ok(Self { pool })
+    }
+
+    pub async fn get_indexed_l1_block(&self) -> Result<u64, IndexerError> {
+        let indexed_l1_block: i64 = sqlx::query_scalar(
             r#"
-            CREATE TABLE IF NOT EXISTS batch (
-                batch_id              INTEGER PRIMARY KEY,
-                sender                TEXT NOT NULL,
-                proposer              TEXT NOT NULL,
-                coinbase              TEXT NOT NULL,
-                propose_tx            TEXT NOT NULL,
-                proposed_at           INTEGER NOT NULL,
-                last_block_id         INTEGER NOT NULL,
-                block_count           INTEGER NOT NULL,
-                propose_fee          TEXT NOT NULL,
-                l2_fee_earned         TEXT,
-                prover                TEXT,
-                prove_tx              TEXT,
-                prove_fee            TEXT,
-                is_sent_by_proposer   BOOLEAN NOT NULL,
-                is_profitable         BOOLEAN,
-                is_proved_by_proposer BOOLEAN
-            );
-            CREATE INDEX IF NOT EXISTS idx_batch_proposed_at ON batch(proposed_at);
-            CREATE INDEX IF NOT EXISTS idx_batch_proposer ON batch(proposer);
-            CREATE INDEX IF NOT EXISTS idx_batch_profitable ON batch(is_profitable);
-            CREATE INDEX IF NOT EXISTS idx_batch_sender ON batch(is_sent_by_proposer);
-            CREATE INDEX IF NOT EXISTS idx_batch_proving_window ON batch(is_proved_by_proposer);
+            SELECT indexed_l1_block FROM status WHERE id = 0
             "#,
         )
-        .execute(&pool)
-        .await?;
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| IndexerError::Database(e.into()))?;
 
-        // Create status table (only one row allowed)
-        sqlx::query(
+        indexed_l1_block.try_into().map_err(|_| {
+            IndexerError::Database(anyhow::anyhow!(
+                "Invalid indexed_l1_block {indexed_l1_block}"
+            ))
+        })
+    }
+
+    /// Starts the transaction that writes a whole indexed range
+    pub async fn begin(&self) -> Result<RangeTx, Error> {
+        Ok(RangeTx {
+            tx: self.pool.begin().await?,
+        })
+    }
+
+    pub async fn get_l1_block_hash(&self, block_number: u64) -> Result<Option<String>, Error> {
+        let block_number: i64 = block_number.try_into()?;
+        let hash = sqlx::query_scalar(
             r#"
-            CREATE TABLE IF NOT EXISTS status (
-                id                  INTEGER PRIMARY KEY CHECK (id = 0),
-                indexed_l1_block    INTEGER NOT NULL,
-                proposed_batch_id   INTEGER NOT NULL,
-                proposed_block_id   INTEGER NOT NULL,
-                proved_batch_id     INTEGER NOT NULL,
-                proved_block_id     INTEGER NOT NULL
-            )
+            SELECT block_hash FROM l1_block WHERE block_number = $1
             "#,
         )
-        .execute(&pool)
+        .bind(block_number)
+        .fetch_optional(&self.pool)
         .await?;
 
-        // Insert status if not exist
-        let status = sqlx::query(
+        Ok(hash)
+    }
+
+    /// Returns stored L1 blocks below `block_number`, newest first
+    pub async fn get_l1_blocks_before(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<(u64, String)>, Error> {
+        let block_number: i64 = block_number.try_into()?;
+        let rows: Vec<(i64, String)> = sqlx::query_as(
             r#"
-            SELECT id FROM status WHERE id = 0
+            SELECT block_number, block_hash FROM l1_block
+            WHERE block_number < $1
+            ORDER BY block_number DESC
             "#,
         )
-        .fetch_optional(&pool)
+        .bind(block_number)
+        .fetch_all(&self.pool)
         .await?;
-        if status.is_none() {
+
+        rows.into_iter()
+            .map(|(number, hash)| Ok((number.try_into()?, hash)))
+            .collect()
+    }
+
+    /// Replaces the verifier registry with the configured entries
+    pub async fn replace_verifiers(&self, verifiers: &[VerifierEntry]) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM verifier")
+            .execute(&mut *tx)
+            .await?;
+        for verifier in verifiers {
             sqlx::query(
                 r#"
-                INSERT INTO status (id, indexed_l1_block, proposed_batch_id, proposed_block_id, proved_batch_id, proved_block_id)
-                VALUES (0, 0, 0, 0, 0, 0)
+                INSERT INTO verifier (address, proof_type)
+                VALUES ($1, $2)
+                ON CONFLICT (address) DO UPDATE SET proof_type = excluded.proof_type
                 "#,
             )
-            .execute(&pool)
+            .bind(verifier.verifier.to_string())
+            .bind(&verifier.proof_type)
+            .execute(&mut *tx)
             .await?;
         }
+        tx.commit().await?;
 
-        Ok(Self { pool })
+        Ok(())
     }
 
-    pub async fn get_indexed_l1_block(&self) -> u64 {
-        sqlx::query_as(
+    /// Returns `(version, next_batch_id, last_batch_id)` of every unfinished
+    /// backfill, oldest migration first
+    pub async fn get_pending_backfills(&self) -> Result<Vec<(i64, i64, i64)>, Error> {
+        let backfills = sqlx::query_as(
             r#"
-            SELECT indexed_l1_block FROM status WHERE id = 0
+            SELECT version, next_batch_id, last_batch_id FROM backfill ORDER BY version
             "#,
         )
-        .fetch_one(&self.pool)
-        .await
-        .unwrap_or((0,))
-        .0
-        .try_into()
-        .expect("Cannot convert indexed_l1_block to u64")
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(backfills)
     }
 
-    pub async fn update_status(
+    pub async fn get_batches_between(
         &self,
+        from_batch_id: i64,
+        to_batch_id: i64,
+        limit: i64,
+    ) -> Result<Vec<Batch>, Error> {
+        let batches = sqlx::query_as(
+            r#"
+            SELECT * FROM batch
+            WHERE batch_id BETWEEN $1 AND $2
+            ORDER BY batch_id
+            LIMIT $3
+            "#,
+        )
+        .bind(from_batch_id)
+        .bind(to_batch_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(batches)
+    }
+
+    pub async fn get_batch_by_id(&self, batch_id: i64) -> Result<Option<Batch>, Error> {
+        let batch = sqlx::query_as(
+            r#"
+            SELECT * FROM batch WHERE batch_id = $1
+            "#,
+        )
+        .bind(batch_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(batch)
+    }
+
+    pub async fn get_pending_proofs(&self) -> Result<Vec<PendingProof>, Error> {
+        let proofs = sqlx::query_as(
+            r#"
+            SELECT * FROM pending_proof ORDER BY l1_block, log_index
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(proofs)
+    }
+
+    /// Reverts everything indexed after `l1_block`: removes batches proposed
+    /// later, clears proofs submitted later and rewinds the status cursor.
+    /// Proofs submitted up to `l1_block` for removed batches are not indexed
+    /// again, so they go back to the pending proofs until the batches are.
+    pub async fn rollback(&self, l1_block: u64) -> Result<(), Error> {
+        let l1_block: i64 = l1_block.try_into()?;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM pending_proof WHERE l1_block > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO pending_proof (
+                batch_id, prove_tx, l1_block, l1_timestamp, log_index, verifier,
+                parent_hash, block_hash, state_root, prove_fee, prove_fee_split, prove_sender,
+                prover
+            )
+            SELECT
+                batch_id, prove_tx, l1_block, l1_timestamp, log_index, verifier,
+                parent_hash, block_hash, state_root, prove_fee, prove_fee_split, prove_sender,
+                CASE WHEN prover_attribution = 'event' THEN prover END
+            FROM batch_proof
+            WHERE l1_block <= $1
+                AND batch_id IN (SELECT batch_id FROM batch WHERE propose_l1_block > $1)
+            ON CONFLICT (batch_id, prove_tx) DO NOTHING
+            "#,
+        )
+        .bind(l1_block)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM batch_proof
+            WHERE l1_block > $1
+                OR batch_id IN (SELECT batch_id FROM batch WHERE propose_l1_block > $1)
+            "#,
+        )
+        .bind(l1_block)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM l2_block WHERE batch_id IN (SELECT batch_id FROM batch WHERE propose_l1_block > $1)",
+        )
+        .bind(l1_block)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM batch_info WHERE batch_id IN (SELECT batch_id FROM batch WHERE propose_l1_block > $1)",
+        )
+        .bind(l1_block)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM batch_blob WHERE batch_id IN (SELECT batch_id FROM batch WHERE propose_l1_block > $1)",
+        )
+        .bind(l1_block)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM batch WHERE propose_l1_block > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE batch SET
+                verified_l1_block = NULL,
+                verified_at = NULL,
+                verified_block_hash = NULL
+            WHERE verified_l1_block > $1
+            "#,
+        )
+        .bind(l1_block)
+        .execute(&mut *tx)
+        .await?;
+
+        // point unverified batches and those whose effective proof was removed
+        // to their effective proof among the remaining ones
+        sqlx::query(&format!(
+            r#"
+            UPDATE batch SET prove_tx = {EFFECTIVE_PROVE_TX}
+            WHERE prove_tx IS NOT NULL AND (
+                verified_l1_block IS NULL OR NOT EXISTS (
+                    SELECT 1 FROM batch_proof
+                    WHERE batch_proof.batch_id = batch.batch_id
+                        AND batch_proof.prove_tx = batch.prove_tx
+                )
+            )
+            "#
+        ))
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM proof_conflict WHERE l1_block > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM bond_event WHERE l1_block > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM bond_balance WHERE l1_block > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM tx_cost WHERE l1_block > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM protocol_config WHERE l1_block > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM l1_block WHERE block_number > $1")
+            .bind(l1_block)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE status SET
+                indexed_l1_block = $1,
+                proposed_batch_id = COALESCE((SELECT MAX(batch_id) FROM batch), 0),
+                proposed_block_id = COALESCE((SELECT MAX(last_block_id) FROM batch), 0),
+                proved_batch_id = COALESCE((SELECT MAX(batch_id) FROM batch WHERE prove_tx IS NOT NULL), 0),
+                proved_block_id = COALESCE((SELECT MAX(last_block_id) FROM batch WHERE prove_tx IS NOT NULL), 0),
+                verified_batch_id = COALESCE((SELECT MAX(batch_id) FROM batch WHERE verified_l1_block IS NOT NULL), 0)
+            WHERE id = 0
+            "#,
+        )
+        .bind(l1_block)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Writes of a single indexed range. Nothing is visible to readers, nor
+/// survives a crash, until the range is committed together with its status
+/// cursor.
+pub struct RangeTx {
+    tx: Transaction<'static, Any>,
+}
+
+impl RangeTx {
+    pub async fn commit(self) -> Result<(), Error> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn update_status(
+        &mut self,
         indexed_l1_block: u64,
         proposed_batch_id: u64,
         proposed_block_id: u64,
         proved_batch_id: u64,
         proved_block_id: u64,
+        verified_batch_id: u64,
     ) -> Result<(), Error> {
         let mut query = String::from("UPDATE status SET ");
-        let mut updates = Vec::new();
+        let mut updates: Vec<String> = Vec::new();
         let mut values: Vec<i64> = Vec::new();
 
         if indexed_l1_block != 0 {
-            updates.push("indexed_l1_block = ?");
             values.push(indexed_l1_block.try_into()?);
+            updates.push(format!("indexed_l1_block = ${}", values.len()));
         }
         if proposed_batch_id != 0 {
-            updates.push("proposed_batch_id = ?");
             values.push(proposed_batch_id.try_into()?);
+            updates.push(format!("proposed_batch_id = ${}", values.len()));
         }
         if proposed_block_id != 0 {
-            updates.push("proposed_block_id = ?");
             values.push(proposed_block_id.try_into()?);
+            updates.push(format!("proposed_block_id = ${}", values.len()));
         }
         if proved_batch_id != 0 {
-            updates.push("proved_batch_id = ?");
             values.push(proved_batch_id.try_into()?);
+            updates.push(format!("proved_batch_id = ${}", values.len()));
         }
         if proved_block_id != 0 {
-            updates.push("proved_block_id = ?");
             values.push(proved_block_id.try_into()?);
+            updates.push(format!("proved_block_id = ${}", values.len()));
+        }
+        if verified_batch_id != 0 {
+            values.push(verified_batch_id.try_into()?);
+            updates.push(format!("verified_batch_id = ${}", values.len()));
         }
 
         if updates.is_empty() {
@@ -173,35 +645,38 @@ impl DataBase {
             sql = sql.bind(val);
         }
 
-        sql.execute(&self.pool).await?;
+        sql.execute(&mut *self.tx).await?;
         Ok(())
     }
 
     pub async fn insert_batch(
-        &self,
-        batch: Log<ITaikoInbox::BatchProposed>,
-        tx_hash: String,
-        sender: Address,
-        propose_fee: u128,
+        &mut self,
+        batch: &ProposedBatch,
+        proposal: &BatchProposal,
     ) -> Result<(), Error> {
-        let batch_id: i64 = batch.inner.meta.batchId.try_into()?;
-        let is_sent_by_proposer = sender == batch.inner.info.coinbase;
-        let sender = sender.to_string();
-        let proposer = batch.inner.meta.proposer.to_string();
-        let propose_tx = tx_hash;
-        let proposed_at: i64 = batch.inner.meta.proposedAt.try_into()?;
-        let last_block_id: i64 = batch.inner.info.lastBlockId.try_into()?;
-        let block_count: i64 = batch.inner.info.blocks.len().try_into()?;
-        let propose_fee = propose_fee.to_string();
-        let coinbase = batch.inner.info.coinbase.to_string();
+        let batch_id: i64 = batch.batch_id.try_into()?;
+        let propose_l1_block: i64 = proposal.l1_block.try_into()?;
+        let propose_l1_timestamp: i64 = proposal.l1_timestamp.try_into()?;
+        let is_sent_by_proposer = proposal.sender == batch.coinbase;
+        let sender = proposal.sender.to_string();
+        let proposer = batch.proposer.to_string();
+        let propose_tx = &proposal.tx_hash;
+        let proposed_at: i64 = batch.proposed_at.try_into()?;
+        let last_block_id: i64 = batch.last_block_id.try_into()?;
+        let block_count: i64 = batch.blocks.len().try_into()?;
+        let propose_fee = proposal.fee.to_string();
+        let coinbase = batch.coinbase.to_string();
+        let base_fee_sharing_pctg = i64::from(batch.base_fee_config.sharing_pctg);
 
         let result = sqlx::query(
             r#"
             INSERT INTO batch (
-                batch_id, sender, proposer, coinbase, propose_tx, proposed_at,
-                last_block_id, block_count, propose_fee, is_sent_by_proposer
+                batch_id, sender, proposer, coinbase, propose_tx, propose_l1_block,
+                propose_l1_timestamp, proposed_at, last_block_id, block_count, propose_fee,
+                is_sent_by_proposer, base_fee_sharing_pctg, propose_fee_split, fork
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            ON CONFLICT (batch_id) DO NOTHING
             "#,
         )
         .bind(batch_id)
@@ -209,66 +684,1144 @@ impl DataBase {
         .bind(proposer)
         .bind(coinbase)
         .bind(propose_tx)
+        .bind(propose_l1_block)
+        .bind(propose_l1_timestamp)
         .bind(proposed_at)
         .bind(last_block_id)
         .bind(block_count)
         .bind(propose_fee)
-        .bind(is_sent_by_proposer)
-        .execute(&self.pool)
-        .await;
-
-        match result {
-            Ok(_) => tracing::debug!("Batch inserted: batch_id {}", batch_id),
-            Err(sqlx::error::Error::Database(db_err)) if db_err.is_unique_violation() => {
-                tracing::error!("Duplicate batch_id {}, insert skipped", batch_id);
-            }
-            Err(e) => return Err(e.into()),
+        .bind(i64::from(is_sent_by_proposer))
+        .bind(base_fee_sharing_pctg)
+        .bind(proposal.fee_split.as_str())
+        .bind(batch.fork.as_str())
+        .execute(&mut *self.tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tracing::warn!("Duplicate batch_id {}, insert skipped", batch_id);
+            return Ok(());
         }
+        self.insert_l2_blocks(batch).await?;
+        self.insert_batch_info(batch).await?;
+
+        tracing::debug!("Batch inserted: batch_id {}", batch_id);
 
         Ok(())
     }
 
-    pub async fn get_batch_by_id(&self, batch_id: i64) -> Option<Batch> {
-        match sqlx::query_as(
+    /// Stores the proposal fields that are not part of the batch table, with
+    /// one row per blob hash
+    pub async fn insert_batch_info(&mut self, batch: &ProposedBatch) -> Result<(), Error> {
+        let batch_id: i64 = batch.batch_id.try_into()?;
+        let base_fee_config = &batch.base_fee_config;
+
+        sqlx::query(
             r#"
-            SELECT * FROM batch WHERE batch_id = ?
+            INSERT INTO batch_info (
+                batch_id, info_hash, txs_hash, extra_data, proposed_in, blob_created_in,
+                blob_byte_offset, blob_byte_size, gas_limit, last_block_timestamp,
+                anchor_block_id, anchor_block_hash, adjustment_quotient, sharing_pctg,
+                gas_issuance_per_second, min_gas_excess, max_gas_issuance_per_block
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            ON CONFLICT (batch_id) DO NOTHING
             "#,
         )
         .bind(batch_id)
-        .fetch_optional(&self.pool)
-        .await
-        {
-            Ok(res) => res,
-            Err(e) => {
-                tracing::error!("Error getting batch by id {}: {}", batch_id, e);
-                None
+        .bind(batch.info_hash.to_string())
+        .bind(batch.txs_hash.to_string())
+        .bind(batch.extra_data.to_string())
+        .bind(i64::try_from(batch.proposed_in)?)
+        .bind(i64::try_from(batch.blob_created_in)?)
+        .bind(i64::from(batch.blob_byte_offset))
+        .bind(i64::from(batch.blob_byte_size))
+        .bind(i64::from(batch.gas_limit))
+        .bind(i64::try_from(batch.last_block_timestamp)?)
+        .bind(i64::try_from(batch.anchor_block_id)?)
+        .bind(batch.anchor_block_hash.to_string())
+        .bind(i64::from(base_fee_config.adjustment_quotient))
+        .bind(i64::from(base_fee_config.sharing_pctg))
+        .bind(i64::from(base_fee_config.gas_issuance_per_second))
+        .bind(base_fee_config.min_gas_excess.to_string())
+        .bind(i64::from(base_fee_config.max_gas_issuance_per_block))
+        .execute(&mut *self.tx)
+        .await?;
+
+        for (blob_index, blob_hash) in batch.blob_hashes.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO batch_blob (batch_id, blob_index, blob_hash)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (batch_id, blob_index) DO NOTHING
+                "#,
+            )
+            .bind(batch_id)
+            .bind(i64::try_from(blob_index)?)
+            .bind(blob_hash.to_string())
+            .execute(&mut *self.tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the L2 blocks of a batch. Timestamps are reconstructed backwards
+    /// from lastBlockTimestamp using the time shift of each block.
+    pub async fn insert_l2_blocks(&mut self, batch: &ProposedBatch) -> Result<(), Error> {
+        let batch_id: i64 = batch.batch_id.try_into()?;
+        let first_block_id = batch.first_block_id()?;
+        let total_time_shift: u64 = batch.blocks.iter().map(|block| block.time_shift).sum();
+        let mut timestamp = batch.last_block_timestamp.saturating_sub(total_time_shift);
+        let mut skipped = 0;
+
+        for (block_number, block) in (first_block_id..).zip(batch.blocks.iter()) {
+            timestamp += block.time_shift;
+            let block_number: i64 = block_number.try_into()?;
+            let timestamp: i64 = timestamp.try_into()?;
+            let tx_count = block.tx_count.map(i64::try_from).transpose()?;
+            let signal_count: i64 = block.signal_count.try_into()?;
+            let result = sqlx::query(
+                r#"
+                INSERT INTO l2_block (block_number, batch_id, timestamp, tx_count, signal_count)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (block_number) DO NOTHING
+                "#,
+            )
+            .bind(block_number)
+            .bind(batch_id)
+            .bind(timestamp)
+            .bind(tx_count)
+            .bind(signal_count)
+            .execute(&mut *self.tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                skipped += 1;
             }
         }
+
+        if skipped > 0 {
+            tracing::debug!(
+                "Duplicate L2 blocks of batch_id {}, {} inserts skipped",
+                batch_id,
+                skipped
+            );
+        }
+
+        Ok(())
     }
 
-    pub async fn update_batch(&self, batch: Batch) -> Result<(), Error> {
+    pub async fn update_l2_block_gas(
+        &mut self,
+        block_number: u64,
+        gas_used: u64,
+        base_fee: u64,
+    ) -> Result<(), Error> {
+        let block_number: i64 = block_number.try_into()?;
+        let gas_used: i64 = gas_used.try_into()?;
         sqlx::query(
             r#"
-            UPDATE batch SET
-                l2_fee_earned = ?,
-                prover = ?,
-                prove_tx = ?,
-                prove_fee = ?,
-                is_profitable = ?,
-                is_proved_by_proposer = ?
-            WHERE batch_id = ?
+            UPDATE l2_block SET gas_used = $1, base_fee = $2 WHERE block_number = $3
+            "#,
+        )
+        .bind(gas_used)
+        .bind(base_fee.to_string())
+        .bind(block_number)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_batch_by_id(&mut self, batch_id: i64) -> Result<Option<Batch>, Error> {
+        let batch = sqlx::query_as(
+            r#"
+            SELECT * FROM batch WHERE batch_id = $1
             "#,
         )
+        .bind(batch_id)
+        .fetch_optional(&mut *self.tx)
+        .await?;
+
+        Ok(batch)
+    }
+
+    pub async fn update_batch(&mut self, batch: Batch) -> Result<(), Error> {
+        sqlx::query(&format!(
+            r#"
+            UPDATE batch SET
+                l2_fee_earned = $1,
+                l2_priority_fee = $2,
+                l2_base_fee_share = $3,
+                base_fee_sharing_pctg = $4,
+                prove_tx = {EFFECTIVE_PROVE_TX}
+            WHERE batch_id = $5
+            "#
+        ))
         .bind(batch.l2_fee_earned)
-        .bind(batch.prover)
-        .bind(batch.prove_tx)
-        .bind(batch.prove_fee)
-        .bind(batch.is_profitable)
-        .bind(batch.is_proved_by_proposer)
+        .bind(batch.l2_priority_fee)
+        .bind(batch.l2_base_fee_share)
+        .bind(batch.base_fee_sharing_pctg)
         .bind(batch.batch_id)
-        .execute(&self.pool)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_propose_l1_block(
+        &mut self,
+        batch_id: i64,
+        propose_l1_block: u64,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE batch SET propose_l1_block = $1 WHERE batch_id = $2
+            "#,
+        )
+        .bind(i64::try_from(propose_l1_block)?)
+        .bind(batch_id)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_propose_l1_timestamp(
+        &mut self,
+        batch_id: i64,
+        propose_l1_timestamp: u64,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE batch SET propose_l1_timestamp = $1 WHERE batch_id = $2
+            "#,
+        )
+        .bind(i64::try_from(propose_l1_timestamp)?)
+        .bind(batch_id)
+        .execute(&mut *self.tx)
         .await?;
 
         Ok(())
     }
+
+    pub async fn update_propose_fee(
+        &mut self,
+        batch_id: i64,
+        propose_fee: u128,
+        propose_fee_split: ProposeFeeSplit,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE batch SET propose_fee = $1, propose_fee_split = $2 WHERE batch_id = $3
+            "#,
+        )
+        .bind(propose_fee.to_string())
+        .bind(propose_fee_split.as_str())
+        .bind(batch_id)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_prove_fee(
+        &mut self,
+        batch_id: i64,
+        prove_tx: &str,
+        prove_fee: u128,
+        prove_fee_split: ProveFeeSplit,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE batch_proof SET prove_fee = $1, prove_fee_split = $2
+            WHERE batch_id = $3 AND prove_tx = $4
+            "#,
+        )
+        .bind(prove_fee.to_string())
+        .bind(prove_fee_split.as_str())
+        .bind(batch_id)
+        .bind(prove_tx)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the L2 gas used by a batch, None while the gas of any of its
+    /// blocks is not known yet
+    pub async fn get_l2_gas_used(&mut self, batch_id: i64) -> Result<Option<u64>, Error> {
+        let gas_used: Option<i64> = sqlx::query_scalar(
+            r#"
+            SELECT CASE WHEN COUNT(*) > 0 AND COUNT(gas_used) = COUNT(*) THEN CAST(SUM(gas_used) AS BIGINT) END
+            FROM l2_block WHERE batch_id = $1
+            "#,
+        )
+        .bind(batch_id)
+        .fetch_one(&mut *self.tx)
+        .await?;
+
+        Ok(gas_used.map(u64::try_from).transpose()?)
+    }
+
+    /// Re-evaluates `is_profitable` of every proof of a batch after its L2 fee
+    /// revenue changed
+    pub async fn update_proof_profitability(
+        &mut self,
+        batch_id: i64,
+        l2_fee_earned: u128,
+        propose_fee: u128,
+    ) -> Result<(), Error> {
+        let proofs: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT prove_tx, prove_fee FROM batch_proof WHERE batch_id = $1
+            "#,
+        )
+        .bind(batch_id)
+        .fetch_all(&mut *self.tx)
+        .await?;
+
+        for (prove_tx, prove_fee) in proofs {
+            let is_profitable = l2_fee_earned > prove_fee.parse::<u128>()? + propose_fee;
+            sqlx::query(
+                r#"
+                UPDATE batch_proof SET is_profitable = $1 WHERE batch_id = $2 AND prove_tx = $3
+                "#,
+            )
+            .bind(i64::from(is_profitable))
+            .bind(batch_id)
+            .bind(prove_tx)
+            .execute(&mut *self.tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn insert_batch_proof(&mut self, proof: BatchProof) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO batch_proof (
+                batch_id, prove_tx, l1_block, l1_timestamp, log_index, verifier,
+                parent_hash, block_hash, state_root, prove_fee, prove_fee_split, prove_sender,
+                prover, in_proving_window, prover_attribution, is_proved_by_proposer, is_profitable
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            ON CONFLICT (batch_id, prove_tx) DO NOTHING
+            "#,
+        )
+        .bind(proof.batch_id)
+        .bind(&proof.prove_tx)
+        .bind(proof.l1_block)
+        .bind(proof.l1_timestamp)
+        .bind(proof.log_index)
+        .bind(proof.verifier)
+        .bind(proof.parent_hash)
+        .bind(proof.block_hash)
+        .bind(proof.state_root)
+        .bind(proof.prove_fee)
+        .bind(proof.prove_fee_split)
+        .bind(proof.prove_sender)
+        .bind(proof.prover)
+        .bind(proof.in_proving_window.map(i64::from))
+        .bind(proof.prover_attribution.as_str())
+        .bind(i64::from(proof.is_proved_by_proposer))
+        .bind(i64::from(proof.is_profitable))
+        .execute(&mut *self.tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tracing::warn!(
+                "Duplicate proof {} for batch_id {}, insert skipped",
+                proof.prove_tx,
+                proof.batch_id
+            );
+        } else {
+            tracing::debug!(
+                "Batch proof inserted: batch_id {} prove_tx {}",
+                proof.batch_id,
+                proof.prove_tx
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn insert_tx_cost(&mut self, cost: TxCost) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO tx_cost (
+                tx_hash, l1_block, gas_used, effective_gas_price, base_fee_per_gas,
+                priority_fee_per_gas, blob_gas_used, blob_gas_price, blob_count
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (tx_hash) DO NOTHING
+            "#,
+        )
+        .bind(cost.tx_hash)
+        .bind(cost.l1_block)
+        .bind(cost.gas_used)
+        .bind(cost.effective_gas_price)
+        .bind(cost.base_fee_per_gas)
+        .bind(cost.priority_fee_per_gas)
+        .bind(cost.blob_gas_used)
+        .bind(cost.blob_gas_price)
+        .bind(cost.blob_count)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the transactions of all proofs of a batch
+    pub async fn get_prove_txs(&mut self, batch_id: i64) -> Result<Vec<String>, Error> {
+        let prove_txs = sqlx::query_scalar(
+            r#"
+            SELECT prove_tx FROM batch_proof WHERE batch_id = $1 ORDER BY l1_block, log_index
+            "#,
+        )
+        .bind(batch_id)
+        .fetch_all(&mut *self.tx)
+        .await?;
+
+        Ok(prove_txs)
+    }
+
+    pub async fn insert_pending_proof(&mut self, proof: &PendingProof) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO pending_proof (
+                batch_id, prove_tx, l1_block, l1_timestamp, log_index, verifier,
+                parent_hash, block_hash, state_root, prove_fee, prove_fee_split, prove_sender,
+                prover
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (batch_id, prove_tx) DO NOTHING
+            "#,
+        )
+        .bind(proof.batch_id)
+        .bind(&proof.prove_tx)
+        .bind(proof.l1_block)
+        .bind(proof.l1_timestamp)
+        .bind(proof.log_index)
+        .bind(&proof.verifier)
+        .bind(&proof.parent_hash)
+        .bind(&proof.block_hash)
+        .bind(&proof.state_root)
+        .bind(&proof.prove_fee)
+        .bind(&proof.prove_fee_split)
+        .bind(&proof.prove_sender)
+        .bind(&proof.prover)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns pending proofs whose batches are indexed, oldest first
+    pub async fn get_resolvable_pending_proofs(&mut self) -> Result<Vec<PendingProof>, Error> {
+        let proofs = sqlx::query_as(
+            r#"
+            SELECT pending_proof.* FROM pending_proof
+            JOIN batch ON batch.batch_id = pending_proof.batch_id
+            ORDER BY pending_proof.l1_block, pending_proof.log_index
+            "#,
+        )
+        .fetch_all(&mut *self.tx)
+        .await?;
+
+        Ok(proofs)
+    }
+
+    pub async fn delete_pending_proof(
+        &mut self,
+        batch_id: i64,
+        prove_tx: &str,
+    ) -> Result<(), Error> {
+        sqlx::query("DELETE FROM pending_proof WHERE batch_id = $1 AND prove_tx = $2")
+            .bind(batch_id)
+            .bind(prove_tx)
+            .execute(&mut *self.tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deletes pending proofs of batches proposed before the first indexed
+    /// one, which will never be indexed
+    pub async fn delete_unresolvable_pending_proofs(&mut self) -> Result<u64, Error> {
+        let result = sqlx::query(
+            "DELETE FROM pending_proof WHERE batch_id < (SELECT MIN(batch_id) FROM batch)",
+        )
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_pending_proof_count(&mut self) -> Result<i64, Error> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM pending_proof")
+            .fetch_one(&mut *self.tx)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Marks all not yet verified batches up to `batch_id` as verified. The
+    /// block hash of the event is the last block of `batch_id` only.
+    pub async fn verify_batches(
+        &mut self,
+        batch_id: u64,
+        verified_l1_block: u64,
+        verified_at: u64,
+        verified_block_hash: String,
+    ) -> Result<(), Error> {
+        let batch_id: i64 = batch_id.try_into()?;
+        let verified_l1_block: i64 = verified_l1_block.try_into()?;
+        let verified_at: i64 = verified_at.try_into()?;
+
+        sqlx::query(
+            r#"
+            UPDATE batch SET
+                verified_l1_block = $1,
+                verified_at = $2,
+                verified_block_hash = CASE WHEN batch_id = $4 THEN $3 END
+            WHERE batch_id <= $4 AND verified_l1_block IS NULL
+            "#,
+        )
+        .bind(verified_l1_block)
+        .bind(verified_at)
+        .bind(verified_block_hash)
+        .bind(batch_id)
+        .execute(&mut *self.tx)
+        .await?;
+
+        // the verified transition of the batch is known now
+        sqlx::query(&format!(
+            r#"
+            UPDATE batch SET prove_tx = {EFFECTIVE_PROVE_TX}
+            WHERE batch_id = $1 AND prove_tx IS NOT NULL
+            "#
+        ))
+        .bind(batch_id)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// L1 block of the latest indexed verification, 0 if there is none
+    pub async fn get_last_verified_l1_block(&mut self) -> Result<u64, Error> {
+        let verified_l1_block: i64 =
+            sqlx::query_scalar("SELECT COALESCE(MAX(verified_l1_block), 0) FROM batch")
+                .fetch_one(&mut *self.tx)
+                .await?;
+
+        Ok(verified_l1_block.try_into()?)
+    }
+
+    pub async fn insert_proof_conflict(
+        &mut self,
+        conflict: Log<ITaikoInbox::ConflictingProof>,
+        tx_hash: String,
+        l1_block: u64,
+        log_index: u64,
+    ) -> Result<(), Error> {
+        let batch_id: i64 = conflict.inner.batchId.try_into()?;
+        let l1_block: i64 = l1_block.try_into()?;
+        let log_index: i64 = log_index.try_into()?;
+        let old_tran = &conflict.inner.oldTran;
+        let new_tran = &conflict.inner.newTran;
+        let old_created_at: i64 = old_tran.createdAt.to::<u64>().try_into()?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO proof_conflict (
+                batch_id, l1_block, tx_hash, log_index,
+                old_parent_hash, old_block_hash, old_state_root,
+                old_prover, old_in_proving_window, old_created_at,
+                new_parent_hash, new_block_hash, new_state_root
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (tx_hash, log_index) DO NOTHING
+            "#,
+        )
+        .bind(batch_id)
+        .bind(l1_block)
+        .bind(tx_hash)
+        .bind(log_index)
+        .bind(old_tran.parentHash.to_string())
+        .bind(old_tran.blockHash.to_string())
+        .bind(old_tran.stateRoot.to_string())
+        .bind(old_tran.prover.to_string())
+        .bind(i64::from(old_tran.inProvingWindow))
+        .bind(old_created_at)
+        .bind(new_tran.parentHash.to_string())
+        .bind(new_tran.blockHash.to_string())
+        .bind(new_tran.stateRoot.to_string())
+        .execute(&mut *self.tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tracing::warn!(
+                "Duplicate proof conflict for batch_id {}, insert skipped",
+                batch_id
+            );
+        } else {
+            tracing::debug!("Proof conflict inserted: batch_id {}", batch_id);
+        }
+
+        Ok(())
+    }
+
+    pub async fn insert_bond_event(&mut self, event: BondEvent) -> Result<(), Error> {
+        let tx_hash = event.tx_hash;
+        let log_index: i64 = event.log_index.try_into()?;
+        let l1_block: i64 = event.l1_block.try_into()?;
+        let l1_timestamp: i64 = event.l1_timestamp.try_into()?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO bond_event (
+                tx_hash, log_index, l1_block, l1_timestamp, address, kind, amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (tx_hash, log_index) DO NOTHING
+            "#,
+        )
+        .bind(&tx_hash)
+        .bind(log_index)
+        .bind(l1_block)
+        .bind(l1_timestamp)
+        .bind(event.address.to_string())
+        .bind(event.kind.as_str())
+        .bind(event.amount.to_string())
+        .execute(&mut *self.tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tracing::warn!(
+                "Duplicate bond event {} {}, insert skipped",
+                tx_hash,
+                log_index
+            );
+        } else {
+            tracing::debug!("Bond event inserted: {} {}", tx_hash, log_index);
+        }
+
+        Ok(())
+    }
+
+    /// Sums all indexed bond events of `address`, credits minus debits
+    pub async fn get_bond_ledger_balance(&mut self, address: Address) -> Result<I256, Error> {
+        let events: Vec<(String, String)> =
+            sqlx::query_as("SELECT kind, amount FROM bond_event WHERE address = $1")
+                .bind(address.to_string())
+                .fetch_all(&mut *self.tx)
+                .await?;
+
+        events
+            .into_iter()
+            .try_fold(I256::ZERO, |balance, (kind, amount)| {
+                let amount = I256::from_dec_str(&amount)?;
+                Ok(match BondEventKind::from_str(&kind)? {
+                    BondEventKind::Deposited | BondEventKind::Credited => balance + amount,
+                    BondEventKind::Withdrawn | BondEventKind::Debited => balance - amount,
+                })
+            })
+    }
+
+    /// Returns the difference between the on-chain and the ledger balance
+    /// recorded at the first reconciliation of `address`
+    pub async fn get_bond_balance_offset(
+        &mut self,
+        address: Address,
+    ) -> Result<Option<I256>, Error> {
+        let row: Option<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT balance, ledger_balance FROM bond_balance
+            WHERE address = $1
+            ORDER BY l1_block
+            LIMIT 1
+            "#,
+        )
+        .bind(address.to_string())
+        .fetch_optional(&mut *self.tx)
+        .await?;
+
+        row.map(|(balance, ledger_balance)| {
+            Ok(I256::from_dec_str(&balance)? - I256::from_dec_str(&ledger_balance)?)
+        })
+        .transpose()
+    }
+
+    pub async fn update_bond_balance(
+        &mut self,
+        address: Address,
+        l1_block: u64,
+        balance: U256,
+        ledger_balance: I256,
+        drift: I256,
+    ) -> Result<(), Error> {
+        let l1_block: i64 = l1_block.try_into()?;
+        sqlx::query(
+            r#"
+            INSERT INTO bond_balance (address, l1_block, balance, ledger_balance, drift)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (address, l1_block) DO UPDATE SET
+                balance = excluded.balance,
+                ledger_balance = excluded.ledger_balance,
+                drift = excluded.drift
+            "#,
+        )
+        .bind(address.to_string())
+        .bind(l1_block)
+        .bind(balance.to_string())
+        .bind(ledger_balance.to_string())
+        .bind(drift.to_string())
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn insert_l1_block(
+        &mut self,
+        block_number: u64,
+        block_hash: String,
+    ) -> Result<(), Error> {
+        let block_number: i64 = block_number.try_into()?;
+        sqlx::query(
+            r#"
+            INSERT INTO l1_block (block_number, block_hash)
+            VALUES ($1, $2)
+            ON CONFLICT (block_number) DO UPDATE SET block_hash = excluded.block_hash
+            "#,
+        )
+        .bind(block_number)
+        .bind(block_hash)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the last L2 block id of all proposed batches
+    pub async fn get_proposed_block_id(&mut self) -> Result<u64, Error> {
+        let block_id: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(last_block_id), 0) FROM batch")
+            .fetch_one(&mut *self.tx)
+            .await?;
+
+        Ok(block_id.try_into()?)
+    }
+
+    /// Returns the protocol config in force at `l1_block`, or the oldest stored
+    /// one for blocks before the history starts. None while no config is
+    /// stored.
+    pub async fn get_protocol_config(
+        &mut self,
+        l1_block: u64,
+    ) -> Result<Option<ProtocolConfig>, Error> {
+        let config = sqlx::query_as(
+            r#"
+            SELECT
+                proving_window, cooldown_window, liveness_bond_base, liveness_bond_per_block,
+                max_blocks_per_batch, ontake_fork_height, pacaya_fork_height,
+                shasta_fork_height, unzen_fork_height
+            FROM protocol_config
+            WHERE l1_block <= $1 OR l1_block = (SELECT MIN(l1_block) FROM protocol_config)
+            ORDER BY l1_block DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(i64::try_from(l1_block)?)
+        .fetch_optional(&mut *self.tx)
+        .await?;
+
+        Ok(config)
+    }
+
+    /// Records a protocol config in force from `l1_block` on
+    pub async fn insert_protocol_config(
+        &mut self,
+        l1_block: u64,
+        config: &ProtocolConfig,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO protocol_config (
+                l1_block, proving_window, cooldown_window, liveness_bond_base,
+                liveness_bond_per_block, max_blocks_per_batch, ontake_fork_height,
+                pacaya_fork_height, shasta_fork_height, unzen_fork_height
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (l1_block) DO UPDATE SET
+                proving_window = excluded.proving_window,
+                cooldown_window = excluded.cooldown_window,
+                liveness_bond_base = excluded.liveness_bond_base,
+                liveness_bond_per_block = excluded.liveness_bond_per_block,
+                max_blocks_per_batch = excluded.max_blocks_per_batch,
+                ontake_fork_height = excluded.ontake_fork_height,
+                pacaya_fork_height = excluded.pacaya_fork_height,
+                shasta_fork_height = excluded.shasta_fork_height,
+                unzen_fork_height = excluded.unzen_fork_height
+            "#,
+        )
+        .bind(i64::try_from(l1_block)?)
+        .bind(config.proving_window)
+        .bind(config.cooldown_window)
+        .bind(&config.liveness_bond_base)
+        .bind(&config.liveness_bond_per_block)
+        .bind(config.max_blocks_per_batch)
+        .bind(config.ontake_fork_height)
+        .bind(config.pacaya_fork_height)
+        .bind(config.shasta_fork_height)
+        .bind(config.unzen_fork_height)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Moves the backfill of migration `version` past the batches just filled,
+    /// or removes it once `next_batch_id` is past its last batch
+    pub async fn update_backfill(&mut self, version: i64, next_batch_id: i64) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE backfill SET next_batch_id = $1 WHERE version = $2
+            "#,
+        )
+        .bind(next_batch_id)
+        .bind(version)
+        .execute(&mut *self.tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM backfill WHERE version = $1 AND next_batch_id > last_batch_id
+            "#,
+        )
+        .bind(version)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::*;
+    use crate::fork::{BaseFeeConfig, Fork, ProposedBlock};
+
+    async fn insert_batch(
+        db: &DataBase,
+        batch_id: i64,
+        propose_l1_block: i64,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO batch (
+                batch_id, sender, proposer, coinbase, propose_tx, proposed_at, last_block_id,
+                block_count, propose_fee, is_sent_by_proposer, propose_l1_block
+            )
+            VALUES ($1, 'sender', 'proposer', 'coinbase', $2, 0, $1, 1, '0', 1, $3)
+            "#,
+        )
+        .bind(batch_id)
+        .bind(format!("propose_{batch_id}"))
+        .bind(propose_l1_block)
+        .execute(&db.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_proof(
+        db: &DataBase,
+        batch_id: i64,
+        prove_tx: &str,
+        l1_block: i64,
+        prover_attribution: ProverAttribution,
+    ) -> Result<(), Error> {
+        let mut tx = db.begin().await?;
+        tx.insert_batch_proof(BatchProof {
+            batch_id,
+            prove_tx: prove_tx.to_string(),
+            l1_block,
+            l1_timestamp: l1_block * 12,
+            log_index: 0,
+            verifier: "verifier".to_string(),
+            parent_hash: "parent".to_string(),
+            block_hash: "block".to_string(),
+            state_root: "state".to_string(),
+            prove_fee: "100".to_string(),
+            prove_fee_split: Some("even".to_string()),
+            prove_sender: "prove_sender".to_string(),
+            prover: "prover".to_string(),
+            in_proving_window: None,
+            prover_attribution,
+            is_proved_by_proposer: false,
+            is_profitable: false,
+        })
+        .await?;
+        sqlx::query("UPDATE batch SET prove_tx = $1 WHERE batch_id = $2")
+            .bind(prove_tx)
+            .bind(batch_id)
+            .execute(&mut *tx.tx)
+            .await?;
+        tx.commit().await
+    }
+
+    async fn count(db: &DataBase, query: &str) -> Result<i64, Error> {
+        Ok(sqlx::query_scalar(query).fetch_one(&db.pool).await?)
+    }
+
+    #[tokio::test]
+    async fn replayed_proposal_is_skipped() -> Result<(), Error> {
+        let db = DataBase::new("sqlite::memory:").await?;
+        let batch = ProposedBatch {
+            fork: Fork::Pacaya,
+            batch_id: 1,
+            proposer: Address::ZERO,
+            coinbase: Address::ZERO,
+            proposed_at: 0,
+            info_hash: B256::ZERO,
+            txs_hash: B256::ZERO,
+            blob_hashes: vec![B256::ZERO],
+            extra_data: B256::ZERO,
+            proposed_in: 0,
+            blob_created_in: 0,
+            blob_byte_offset: 0,
+            blob_byte_size: 0,
+            gas_limit: 0,
+            last_block_id: 11,
+            last_block_timestamp: 24,
+            anchor_block_id: 0,
+            anchor_block_hash: B256::ZERO,
+            base_fee_config: BaseFeeConfig {
+                adjustment_quotient: 0,
+                sharing_pctg: 0,
+                gas_issuance_per_second: 0,
+                min_gas_excess: 0,
+                max_gas_issuance_per_block: 0,
+            },
+            blocks: (0..2)
+                .map(|_| ProposedBlock {
+                    tx_count: Some(1),
+                    time_shift: 12,
+                    signal_count: 0,
+                })
+                .collect(),
+        };
+        let proposal = BatchProposal {
+            tx_hash: "propose".to_string(),
+            l1_block: 100,
+            l1_timestamp: 1200,
+            sender: Address::ZERO,
+            fee: 0,
+            fee_split: ProposeFeeSplit::Even,
+        };
+
+        for _ in 0..2 {
+            let mut tx = db.begin().await?;
+            tx.insert_batch(&batch, &proposal).await?;
+            tx.insert_l2_blocks(&batch).await?;
+            tx.commit().await?;
+        }
+
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM batch").await?, 1);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM l2_block").await?, 2);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM batch_blob").await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rollback_reverts_everything_indexed_after_the_block() -> Result<(), Error> {
+        rollback_everything_indexed_after_the_block(&DataBase::new("sqlite::memory:").await?).await
+    }
+
+    #[tokio::test]
+    async fn rollback_reverts_everything_indexed_after_the_block_on_postgres() -> Result<(), Error>
+    {
+        let Some(database_url) = migrations::tests::postgres_url().await? else {
+            return Ok(());
+        };
+        rollback_everything_indexed_after_the_block(&DataBase::new(&database_url).await?).await
+    }
+
+    async fn rollback_everything_indexed_after_the_block(db: &DataBase) -> Result<(), Error> {
+        insert_batch(db, 1, 100).await?;
+        insert_batch(db, 2, 100).await?;
+        insert_batch(db, 3, 120).await?;
+        insert_proof(db, 1, "prove_1", 105, ProverAttribution::Transition).await?;
+        insert_proof(db, 1, "prove_1b", 118, ProverAttribution::Transition).await?;
+        insert_proof(db, 2, "prove_2", 112, ProverAttribution::Transition).await?;
+        sqlx::query("UPDATE batch SET verified_l1_block = $1 WHERE batch_id = $2")
+            .bind(108)
+            .bind(1)
+            .execute(&db.pool)
+            .await?;
+        sqlx::query("UPDATE batch SET verified_l1_block = $1 WHERE batch_id = $2")
+            .bind(114)
+            .bind(2)
+            .execute(&db.pool)
+            .await?;
+        let mut tx = db.begin().await?;
+        tx.insert_l1_block(105, "hash_105".to_string()).await?;
+        tx.insert_l1_block(120, "hash_120".to_string()).await?;
+        tx.insert_pending_proof(&PendingProof {
+            batch_id: 9,
+            prove_tx: "prove_9".to_string(),
+            l1_block: 119,
+            l1_timestamp: 0,
+            log_index: 0,
+            verifier: "verifier".to_string(),
+            parent_hash: "parent".to_string(),
+            block_hash: "block".to_string(),
+            state_root: "state".to_string(),
+            prove_fee: "0".to_string(),
+            prove_fee_split: None,
+            prove_sender: "prove_sender".to_string(),
+            prover: None,
+        })
+        .await?;
+        tx.update_status(120, 3, 3, 2, 2, 2).await?;
+        tx.commit().await?;
+
+        db.rollback(110).await?;
+
+        assert!(db.get_batch_by_id(3).await?.is_none());
+        // the effective proof of batch 1 moves back to the one left
+        let batch = db
+            .get_batch_by_id(1)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Batch 1 not found"))?;
+        assert_eq!(batch.prove_tx.as_deref(), Some("prove_1"));
+        assert_eq!(batch.verified_l1_block, Some(108));
+        let batch = db
+            .get_batch_by_id(2)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Batch 2 not found"))?;
+        assert_eq!(batch.prove_tx, None);
+        assert_eq!(batch.verified_l1_block, None);
+        assert_eq!(count(db, "SELECT COUNT(*) FROM batch_proof").await?, 1);
+        assert_eq!(count(db, "SELECT COUNT(*) FROM pending_proof").await?, 0);
+        assert_eq!(
+            db.get_l1_block_hash(105).await?.as_deref(),
+            Some("hash_105")
+        );
+        assert_eq!(db.get_l1_block_hash(120).await?, None);
+
+        let status: (i64, i64, i64, i64) = sqlx::query_as(
+            "SELECT indexed_l1_block, proposed_batch_id, proved_batch_id, verified_batch_id FROM status",
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        assert_eq!(status, (110, 2, 1, 1));
+        assert_eq!(db.get_indexed_l1_block().await?, 110);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn verification_selects_the_proof_of_the_verified_transition() -> Result<(), Error> {
+        let db = DataBase::new("sqlite::memory:").await?;
+        insert_batch(&db, 1, 100).await?;
+        insert_proof(&db, 1, "prove_1", 105, ProverAttribution::Transition).await?;
+        insert_proof(&db, 1, "prove_1b", 110, ProverAttribution::Transition).await?;
+        sqlx::query("UPDATE batch_proof SET block_hash = $1 WHERE prove_tx = $2")
+            .bind("0xaa")
+            .bind("prove_1")
+            .execute(&db.pool)
+            .await?;
+
+        let mut tx = db.begin().await?;
+        tx.verify_batches(1, 120, 1440, "0xaa".to_string()).await?;
+        tx.commit().await?;
+
+        // the latest proof is for a transition that was not verified
+        let batch = db
+            .get_batch_by_id(1)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Batch 1 not found"))?;
+        assert_eq!(batch.prove_tx.as_deref(), Some("prove_1"));
+
+        // the latest proof is effective again once the verification is rolled back
+        db.rollback(115).await?;
+        let batch = db
+            .get_batch_by_id(1)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Batch 1 not found"))?;
+        assert_eq!(batch.prove_tx.as_deref(), Some("prove_1b"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rollback_keeps_the_bond_balance_offset() -> Result<(), Error> {
+        let db = DataBase::new("sqlite::memory:").await?;
+        let address = Address::repeat_byte(1);
+        let mut tx = db.begin().await?;
+        tx.update_bond_balance(
+            address,
+            100,
+            U256::from(50),
+            I256::try_from(20)?,
+            I256::ZERO,
+        )
+        .await?;
+        tx.update_bond_balance(
+            address,
+            120,
+            U256::from(60),
+            I256::try_from(25)?,
+            I256::try_from(5)?,
+        )
+        .await?;
+        tx.commit().await?;
+
+        db.rollback(110).await?;
+
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM bond_balance").await?, 1);
+        let mut tx = db.begin().await?;
+        assert_eq!(
+            tx.get_bond_balance_offset(address).await?,
+            Some(I256::try_from(30)?)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rollback_requeues_surviving_proofs_of_removed_batches() -> Result<(), Error> {
+        let db = DataBase::new("sqlite::memory:").await?;
+        insert_batch(&db, 1, 100).await?;
+        insert_proof(&db, 1, "prove_1", 105, ProverAttribution::ProvingWindow).await?;
+        insert_batch(&db, 2, 120).await?;
+        insert_proof(&db, 2, "prove_2", 110, ProverAttribution::Event).await?;
+
+        db.rollback(115).await?;
+
+        assert!(db.get_batch_by_id(2).await?.is_none());
+        assert_eq!(
+            count(&db, "SELECT COUNT(*) FROM batch_proof WHERE batch_id = 2").await?,
+            0
+        );
+        let pending = db.get_pending_proofs().await?;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].batch_id, 2);
+        assert_eq!(pending[0].l1_block, 110);
+        assert_eq!(pending[0].prove_sender, "prove_sender");
+        assert_eq!(pending[0].prove_fee_split.as_deref(), Some("even"));
+        assert_eq!(pending[0].prover.as_deref(), Some("prover"));
+
+        // the proof is applied again once the batch is proposed again
+        insert_batch(&db, 2, 121).await?;
+        let mut tx = db.begin().await?;
+        assert_eq!(tx.get_resolvable_pending_proofs().await?.len(), 1);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,258 @@
+use std::time::Duration;
+
+use alloy::transports::{TransportError, TransportErrorKind};
+use anyhow::Error;
+use tokio::time::sleep;
+
+/// Errors that stop an indexing step, classified by how the indexer reacts to them
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    /// Transient RPC or HTTP failure, retried with backoff
+    #[error("{0:#}")]
+    Rpc(Error),
+    /// The provider rejected the request because of rate limiting, retried with a longer backoff
+    #[error("{0:#}")]
+    RateLimited(Error),
+    /// The provider rejected eth_getLogs because the block range or the result is too large
+    #[error("{0:#}")]
+    LogRangeTooLarge(Error),
+    /// An event or a call result could not be decoded
+    #[error("{0:#}")]
+    Decode(Error),
+    /// Database failure, retried only if the database is busy
+    #[error("{0:#}")]
+    Database(Error),
+    /// Any other unrecoverable error
+    #[error("{0:#}")]
+    Fatal(Error),
+}
+
+impl IndexerError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IndexerError::Rpc(_) => "RPC error",
+            IndexerError::RateLimited(_) => "Rate limited",
+            IndexerError::LogRangeTooLarge(_) => "Log range too large",
+            IndexerError::Decode(_) => "Decode error",
+            IndexerError::Database(_) => "Database error",
+            IndexerError::Fatal(_) => "Fatal error",
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            IndexerError::Rpc(_)
+            | IndexerError::RateLimited(_)
+            | IndexerError::LogRangeTooLarge(_) => true,
+            IndexerError::Database(e) => e
+                .chain()
+                .filter_map(|cause| cause.downcast_ref::<sqlx::Error>())
+                .any(is_transient_db_error),
+            IndexerError::Decode(_) | IndexerError::Fatal(_) => false,
+        }
+    }
+
+    /// Process exit code used when the error stops the indexer
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            IndexerError::Fatal(_) => 1,
+            IndexerError::Rpc(_)
+            | IndexerError::RateLimited(_)
+            | IndexerError::LogRangeTooLarge(_) => 2,
+            IndexerError::Decode(_) => 3,
+            IndexerError::Database(_) => 4,
+        }
+    }
+}
+
+enum ErrorKind {
+    Rpc,
+    RateLimited,
+    LogRangeTooLarge,
+    Decode,
+    Database,
+    Fatal,
+}
+
+impl From<Error> for IndexerError {
+    fn from(error: Error) -> Self {
+        // the outermost classified cause decides, an explicit Fatal included
+        let kind = error.chain().find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<IndexerError>() {
+                return Some(match e {
+                    IndexerError::Rpc(_) => ErrorKind::Rpc,
+                    IndexerError::RateLimited(_) => ErrorKind::RateLimited,
+                    IndexerError::LogRangeTooLarge(_) => ErrorKind::LogRangeTooLarge,
+                    IndexerError::Decode(_) => ErrorKind::Decode,
+                    IndexerError::Database(_) => ErrorKind::Database,
+                    IndexerError::Fatal(_) => ErrorKind::Fatal,
+                });
+            }
+            if let Some(e) = cause.downcast_ref::<TransportError>() {
+                return Some(transport_error_kind(e));
+            }
+            if let Some(e) = cause.downcast_ref::<alloy::contract::Error>() {
+                return Some(match e {
+                    alloy::contract::Error::TransportError(e) => transport_error_kind(e),
+                    _ => ErrorKind::Decode,
+                });
+            }
+            if cause.is::<alloy::sol_types::Error>() {
+                return Some(ErrorKind::Decode);
+            }
+            if cause.is::<sqlx::Error>() {
+                return Some(ErrorKind::Database);
+            }
+            None
+        });
+
+        match kind {
+            Some(ErrorKind::Rpc) => IndexerError::Rpc(error),
+            Some(ErrorKind::RateLimited) => IndexerError::RateLimited(error),
+            Some(ErrorKind::LogRangeTooLarge) => IndexerError::LogRangeTooLarge(error),
+            Some(ErrorKind::Decode) => IndexerError::Decode(error),
+            Some(ErrorKind::Database) => IndexerError::Database(error),
+            Some(ErrorKind::Fatal) | None => IndexerError::Fatal(error),
+        }
+    }
+}
+
+impl From<sqlx::Error> for IndexerError {
+    fn from(error: sqlx::Error) -> Self {
+        IndexerError::Database(error.into())
+    }
+}
+
+/// Messages used by providers to reject eth_getLogs over too many blocks or results
+const LOG_RANGE_ERRORS: &[&str] = &[
+    "block range",
+    "range too large",
+    "range is too large",
+    "range too wide",
+    "more than 10000 results",
+    "query returned more than",
+    "response size",
+    "max results",
+    "too many logs",
+];
+
+/// Classifies an error of eth_getLogs, where a rejected range or result size
+/// means the range has to shrink. Other requests never fail for that reason,
+/// so their errors are classified by the rest of the message only.
+pub fn log_range_error(error: TransportError) -> Error {
+    if let TransportError::ErrorResp(payload) = &error {
+        let message = payload.message.to_lowercase();
+        if LOG_RANGE_ERRORS.iter().any(|e| message.contains(e)) {
+            return IndexerError::LogRangeTooLarge(error.into()).into();
+        }
+    }
+    error.into()
+}
+
+fn transport_error_kind(error: &TransportError) -> ErrorKind {
+    let rate_limited = match error {
+        TransportError::ErrorResp(payload) => {
+            payload.is_retry_err() && payload.message != "header not found"
+        }
+        TransportError::Transport(TransportErrorKind::HttpError(e)) => e.is_rate_limit_err(),
+        _ => false,
+    };
+    if rate_limited {
+        ErrorKind::RateLimited
+    } else {
+        ErrorKind::Rpc
+    }
+}
+
+fn is_transient_db_error(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::PoolTimedOut | sqlx::Error::Io(_) => true,
+        // SQLITE_BUSY and SQLITE_LOCKED
+        sqlx::Error::Database(e) => matches!(e.code().as_deref(), Some("5") | Some("6")),
+        _ => false,
+    }
+}
+
+/// Exponential backoff with jitter between retries of failed requests
+pub struct Backoff {
+    attempt: u32,
+    max_delay: Duration,
+}
+
+impl Backoff {
+    const BASE_DELAY: Duration = Duration::from_secs(1);
+
+    pub fn new(max_delay: Duration) -> Self {
+        Self {
+            attempt: 0,
+            max_delay,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    pub fn next_delay(&mut self, error: &IndexerError) -> Duration {
+        // rate limits need a longer pause than other transient failures
+        let exponent = match error {
+            IndexerError::RateLimited(_) => self.attempt.saturating_add(2),
+            _ => self.attempt,
+        };
+        self.attempt = self.attempt.saturating_add(1);
+
+        let delay = Self::BASE_DELAY
+            .saturating_mul(1u32 << exponent.min(16))
+            .min(self.max_delay);
+        // half of the delay is fixed and half is random
+        let half = delay / 2;
+        let jitter_ms = rand::random_range(0..=u64::try_from(half.as_millis()).unwrap_or(u64::MAX));
+        half + Duration::from_millis(jitter_ms)
+    }
+}
+
+/// Runs `op` until it succeeds or fails with an error that cannot be retried
+pub async fn retry<T, F, Fut>(
+    description: &str,
+    max_delay: Duration,
+    mut op: F,
+) -> Result<T, IndexerError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut backoff = Backoff::new(max_delay);
+    loop {
+        match op().await.map_err(IndexerError::from) {
+            Ok(value) => return Ok(value),
+            Err(e) if e.is_retryable() => {
+                let delay = backoff.next_delay(&e);
+                tracing::warn!("{description}: {}: {e}, retrying in {delay:?}", e.kind());
+                sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn keeps_the_kind_of_the_outermost_indexer_error() {
+        let rpc = IndexerError::Rpc(anyhow!("connection reset"));
+        let fatal = Error::new(IndexerError::Fatal(
+            Error::new(rpc).context("fetching logs"),
+        ));
+        let error = IndexerError::from(fatal.context("indexing range"));
+        assert!(matches!(error, IndexerError::Fatal(_)));
+        assert!(!error.is_retryable());
+
+        let range = IndexerError::LogRangeTooLarge(anyhow!("block range too large"));
+        let error = IndexerError::from(Error::new(range).context("fetching logs"));
+        assert!(matches!(error, IndexerError::LogRangeTooLarge(_)));
+    }
+}
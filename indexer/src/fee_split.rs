@@ -0,0 +1,188 @@
+use std::str::FromStr;
+
+use anyhow::Error;
+
+/// Rule to split the fee of an L1 transaction between the batches it
+/// proposes or proves
+pub trait FeeSplit: Copy {
+    /// Rule applied when no batch has any weight
+    const EVEN: Self;
+
+    fn as_str(&self) -> &'static str;
+}
+
+/// Rule to split the fee of a proposal transaction between its batches
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposeFeeSplit {
+    Even,
+    BlobBytes,
+    BlockCount,
+}
+
+impl FeeSplit for ProposeFeeSplit {
+    const EVEN: Self = ProposeFeeSplit::Even;
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ProposeFeeSplit::Even => "even",
+            ProposeFeeSplit::BlobBytes => "blob_bytes",
+            ProposeFeeSplit::BlockCount => "block_count",
+        }
+    }
+}
+
+impl FromStr for ProposeFeeSplit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "even" => Ok(ProposeFeeSplit::Even),
+            "blob_bytes" => Ok(ProposeFeeSplit::BlobBytes),
+            "block_count" => Ok(ProposeFeeSplit::BlockCount),
+            _ => Err(anyhow::anyhow!("Unknown propose fee split {s}")),
+        }
+    }
+}
+
+/// Rule to split the fee of a proof transaction between its batches
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProveFeeSplit {
+    Even,
+    BlockCount,
+    L2Gas,
+}
+
+impl FeeSplit for ProveFeeSplit {
+    const EVEN: Self = ProveFeeSplit::Even;
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ProveFeeSplit::Even => "even",
+            ProveFeeSplit::BlockCount => "block_count",
+            ProveFeeSplit::L2Gas => "l2_gas",
+        }
+    }
+}
+
+impl FromStr for ProveFeeSplit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "even" => Ok(ProveFeeSplit::Even),
+            "block_count" => Ok(ProveFeeSplit::BlockCount),
+            "l2_gas" => Ok(ProveFeeSplit::L2Gas),
+            _ => Err(anyhow::anyhow!("Unknown prove fee split {s}")),
+        }
+    }
+}
+
+/// Splits `fee` proportionally to `weights` and returns the rule that was
+/// applied, which falls back to the even split when all weights are zero. The wei left
+/// over by rounding down go one each to the first shares, so callers pass the
+/// weights in batch id order.
+pub fn split_fee<S: FeeSplit>(split: S, fee: u128, weights: &[u64]) -> (S, Vec<u128>) {
+    let (split, weights): (S, Vec<u128>) = if weights.iter().all(|weight| *weight == 0) {
+        (S::EVEN, vec![1; weights.len()])
+    } else {
+        (split, weights.iter().copied().map(u128::from).collect())
+    };
+    let total_weight: u128 = weights.iter().sum();
+    if total_weight == 0 {
+        return (split, Vec::new());
+    }
+
+    // fee * weight can overflow, the quotient and remainder parts can't as
+    // long as the total weight of a transaction stays below 2^64
+    let (quotient, remainder) = (fee / total_weight, fee % total_weight);
+    let mut shares: Vec<u128> = weights
+        .iter()
+        .map(|weight| quotient * weight + remainder * weight / total_weight)
+        .collect();
+    let leftover = fee - shares.iter().sum::<u128>();
+    for share in shares
+        .iter_mut()
+        .take(usize::try_from(leftover).unwrap_or(usize::MAX))
+    {
+        *share += 1;
+    }
+
+    (split, shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn even_split_gives_leftover_to_first_shares() {
+        assert_eq!(
+            split_fee(ProveFeeSplit::Even, 100, &[1, 1, 1]),
+            (ProveFeeSplit::Even, vec![34, 33, 33])
+        );
+    }
+
+    #[test]
+    fn weighted_split_is_proportional() {
+        assert_eq!(
+            split_fee(ProposeFeeSplit::BlobBytes, 1000, &[1, 3]),
+            (ProposeFeeSplit::BlobBytes, vec![250, 750])
+        );
+        assert_eq!(
+            split_fee(ProveFeeSplit::L2Gas, 10, &[2, 1]),
+            (ProveFeeSplit::L2Gas, vec![7, 3])
+        );
+    }
+
+    #[test]
+    fn weighted_split_does_not_overflow() {
+        let (_, shares) = split_fee(ProveFeeSplit::L2Gas, u128::MAX, &[u64::MAX, 1]);
+        assert_eq!(shares.iter().sum::<u128>(), u128::MAX);
+    }
+
+    #[test]
+    fn zero_weights_fall_back_to_even() {
+        assert_eq!(
+            split_fee(ProposeFeeSplit::BlockCount, 9, &[0, 0, 0]),
+            (ProposeFeeSplit::Even, vec![3, 3, 3])
+        );
+    }
+
+    #[test]
+    fn single_share_gets_the_whole_fee() {
+        assert_eq!(
+            split_fee(ProveFeeSplit::BlockCount, 12345, &[7]),
+            (ProveFeeSplit::BlockCount, vec![12345])
+        );
+    }
+
+    #[test]
+    fn fee_smaller_than_share_count() {
+        assert_eq!(
+            split_fee(ProveFeeSplit::Even, 2, &[1, 1, 1, 1]),
+            (ProveFeeSplit::Even, vec![1, 1, 0, 0])
+        );
+    }
+
+    #[test]
+    fn no_shares() {
+        assert_eq!(
+            split_fee(ProposeFeeSplit::Even, 100, &[]),
+            (ProposeFeeSplit::Even, Vec::new())
+        );
+    }
+
+    #[test]
+    fn parses_the_rules_of_each_transaction_kind() {
+        assert_eq!(
+            "blob_bytes".parse::<ProposeFeeSplit>().ok(),
+            Some(ProposeFeeSplit::BlobBytes)
+        );
+        assert!("l2_gas".parse::<ProposeFeeSplit>().is_err());
+        assert_eq!(
+            "l2_gas".parse::<ProveFeeSplit>().ok(),
+            Some(ProveFeeSplit::L2Gas)
+        );
+        assert!("blob_bytes".parse::<ProveFeeSplit>().is_err());
+    }
+}
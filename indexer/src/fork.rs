@@ -0,0 +1,631 @@
+use std::str::FromStr;
+
+use alloy::{
+    primitives::{Address, B256, keccak256},
+    rpc::types::Log,
+    sol_types::{SolEvent, SolValue},
+};
+use anyhow::Error;
+
+use crate::{
+    db::ProtocolConfig,
+    error::IndexerError,
+    taiko_inbox_binding::{ITaikoInbox, ITaikoL1Ontake},
+    verifier_registry::Verifier,
+};
+
+/// Protocol fork of the inbox. Every fork emits its own proposal, proof and
+/// verification events, which are decoded into the fork independent models
+/// below so that the rest of the indexer doesn't depend on their layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fork {
+    Ontake,
+    Pacaya,
+    Shasta,
+}
+
+impl Fork {
+    /// Forks whose events can be decoded, oldest first. Shasta is not, its
+    /// inbox events have no final ABI yet, so the indexer stops with a fatal
+    /// error before the first range that proposes Shasta blocks.
+    pub const DECODABLE: [Fork; 2] = [Fork::Ontake, Fork::Pacaya];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fork::Ontake => "ontake",
+            Fork::Pacaya => "pacaya",
+            Fork::Shasta => "shasta",
+        }
+    }
+
+    /// Fork of an L2 block according to the fork heights of `config`. A Shasta
+    /// height of zero means the fork is not scheduled yet.
+    pub fn of_block(block_id: u64, config: &ProtocolConfig) -> Fork {
+        let height = |height: i64| u64::try_from(height).unwrap_or_default();
+        let shasta = height(config.shasta_fork_height);
+        if shasta > 0 && block_id >= shasta {
+            Fork::Shasta
+        } else if block_id >= height(config.pacaya_fork_height) {
+            Fork::Pacaya
+        } else {
+            Fork::Ontake
+        }
+    }
+
+    pub fn proposed_signature(&self) -> Option<B256> {
+        match self {
+            Fork::Ontake => Some(ITaikoL1Ontake::BlockProposedV2::SIGNATURE_HASH),
+            Fork::Pacaya => Some(ITaikoInbox::BatchProposed::SIGNATURE_HASH),
+            Fork::Shasta => None,
+        }
+    }
+
+    pub fn proved_signature(&self) -> Option<B256> {
+        match self {
+            Fork::Ontake => Some(ITaikoL1Ontake::TransitionProvedV2::SIGNATURE_HASH),
+            Fork::Pacaya => Some(ITaikoInbox::BatchesProved::SIGNATURE_HASH),
+            Fork::Shasta => None,
+        }
+    }
+
+    pub fn verified_signature(&self) -> Option<B256> {
+        match self {
+            Fork::Ontake => Some(ITaikoL1Ontake::BlockVerifiedV2::SIGNATURE_HASH),
+            Fork::Pacaya => Some(ITaikoInbox::BatchesVerified::SIGNATURE_HASH),
+            Fork::Shasta => None,
+        }
+    }
+
+    /// Decodes a proposal event of this fork
+    pub fn decode_proposed(&self, log: &Log) -> Result<ProposedBatch, Error> {
+        match self {
+            Fork::Ontake => {
+                let meta = log
+                    .log_decode::<ITaikoL1Ontake::BlockProposedV2>()?
+                    .inner
+                    .data
+                    .meta;
+                Ok(ProposedBatch::from_ontake(meta))
+            }
+            Fork::Pacaya => {
+                let proposed = log.log_decode::<ITaikoInbox::BatchProposed>()?.inner.data;
+                ProposedBatch::from_pacaya(proposed)
+            }
+            Fork::Shasta => Err(self.unsupported()),
+        }
+    }
+
+    /// Decodes a proof event of this fork
+    pub fn decode_proved(&self, log: &Log) -> Result<ProvedBatches, Error> {
+        match self {
+            Fork::Ontake => {
+                let proved = log
+                    .log_decode::<ITaikoL1Ontake::TransitionProvedV2>()?
+                    .inner
+                    .data;
+                Ok(ProvedBatches {
+                    verifier: Verifier::Tier(proved.tier).to_string(),
+                    transitions: vec![BatchTransition {
+                        batch_id: proved.blockId.try_into()?,
+                        parent_hash: proved.tran.parentHash,
+                        block_hash: proved.tran.blockHash,
+                        state_root: proved.tran.stateRoot,
+                        prover: Some(proved.prover),
+                    }],
+                })
+            }
+            Fork::Pacaya => {
+                let proved = log.log_decode::<ITaikoInbox::BatchesProved>()?.inner.data;
+                Ok(ProvedBatches {
+                    verifier: proved.verifier.to_string(),
+                    transitions: proved
+                        .batchIds
+                        .iter()
+                        .zip(proved.transitions.iter())
+                        .map(|(batch_id, transition)| BatchTransition {
+                            batch_id: *batch_id,
+                            parent_hash: transition.parentHash,
+                            block_hash: transition.blockHash,
+                            state_root: transition.stateRoot,
+                            prover: None,
+                        })
+                        .collect(),
+                })
+            }
+            Fork::Shasta => Err(self.unsupported()),
+        }
+    }
+
+    /// Decodes a verification event of this fork
+    pub fn decode_verified(&self, log: &Log) -> Result<VerifiedBatch, Error> {
+        match self {
+            Fork::Ontake => {
+                let verified = log
+                    .log_decode::<ITaikoL1Ontake::BlockVerifiedV2>()?
+                    .inner
+                    .data;
+                Ok(VerifiedBatch {
+                    batch_id: verified.blockId.try_into()?,
+                    block_hash: verified.blockHash,
+                })
+            }
+            Fork::Pacaya => {
+                let verified = log.log_decode::<ITaikoInbox::BatchesVerified>()?.inner.data;
+                Ok(VerifiedBatch {
+                    batch_id: verified.batchId,
+                    block_hash: verified.blockHash,
+                })
+            }
+            Fork::Shasta => Err(self.unsupported()),
+        }
+    }
+
+    fn unsupported(&self) -> Error {
+        IndexerError::Decode(anyhow::anyhow!(
+            "Events of the {} fork are not supported",
+            self.as_str()
+        ))
+        .into()
+    }
+}
+
+impl FromStr for Fork {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ontake" => Ok(Fork::Ontake),
+            "pacaya" => Ok(Fork::Pacaya),
+            "shasta" => Ok(Fork::Shasta),
+            _ => Err(anyhow::anyhow!("Unknown fork {s}")),
+        }
+    }
+}
+
+/// Event signatures of every decodable fork, as returned by `signature`
+pub fn signatures(signature: fn(&Fork) -> Option<B256>) -> Vec<B256> {
+    Fork::DECODABLE.iter().filter_map(signature).collect()
+}
+
+/// Decodable fork that emitted `log` with the given kind of event, None for
+/// any other event
+pub fn fork_of_log(log: &Log, signature: fn(&Fork) -> Option<B256>) -> Option<Fork> {
+    let topic = log.topic0()?;
+    Fork::DECODABLE
+        .into_iter()
+        .find(|fork| signature(fork).as_ref() == Some(topic))
+}
+
+/// Base fee parameters the L2 blocks of a batch were built with
+pub struct BaseFeeConfig {
+    pub adjustment_quotient: u8,
+    pub sharing_pctg: u8,
+    pub gas_issuance_per_second: u32,
+    pub min_gas_excess: u64,
+    pub max_gas_issuance_per_block: u32,
+}
+
+/// L2 block of a proposed batch
+pub struct ProposedBlock {
+    /// Number of transactions in the block params, not part of Ontake events
+    pub tx_count: Option<u64>,
+    pub time_shift: u64,
+    pub signal_count: u64,
+}
+
+/// Batch proposal of any fork. Ontake proposed single blocks, which are
+/// batches of one block with the block id as batch id.
+pub struct ProposedBatch {
+    pub fork: Fork,
+    pub batch_id: u64,
+    pub proposer: Address,
+    pub coinbase: Address,
+    pub proposed_at: u64,
+    /// Hash of the batch info on Pacaya, of the block metadata on Ontake
+    pub info_hash: B256,
+    /// Hash of the transaction list on Pacaya, of the blob or calldata
+    /// transaction list on Ontake
+    pub txs_hash: B256,
+    pub blob_hashes: Vec<B256>,
+    pub extra_data: B256,
+    pub proposed_in: u64,
+    pub blob_created_in: u64,
+    pub blob_byte_offset: u32,
+    pub blob_byte_size: u32,
+    pub gas_limit: u32,
+    pub last_block_id: u64,
+    pub last_block_timestamp: u64,
+    pub anchor_block_id: u64,
+    pub anchor_block_hash: B256,
+    pub base_fee_config: BaseFeeConfig,
+    pub blocks: Vec<ProposedBlock>,
+}
+
+impl ProposedBatch {
+    fn from_pacaya(proposed: ITaikoInbox::BatchProposed) -> Result<Self, Error> {
+        let info = proposed.info;
+        let base_fee_config = info.baseFeeConfig;
+        Ok(Self {
+            fork: Fork::Pacaya,
+            batch_id: proposed.meta.batchId,
+            proposer: proposed.meta.proposer,
+            coinbase: info.coinbase,
+            proposed_at: proposed.meta.proposedAt,
+            info_hash: proposed.meta.infoHash,
+            txs_hash: info.txsHash,
+            blob_hashes: info.blobHashes,
+            extra_data: info.extraData,
+            proposed_in: info.proposedIn,
+            blob_created_in: info.blobCreatedIn,
+            blob_byte_offset: info.blobByteOffset,
+            blob_byte_size: info.blobByteSize,
+            gas_limit: info.gasLimit,
+            last_block_id: info.lastBlockId,
+            last_block_timestamp: info.lastBlockTimestamp,
+            anchor_block_id: info.anchorBlockId,
+            anchor_block_hash: info.anchorBlockHash,
+            base_fee_config: BaseFeeConfig {
+                adjustment_quotient: base_fee_config.adjustmentQuotient,
+                sharing_pctg: base_fee_config.sharingPctg,
+                gas_issuance_per_second: base_fee_config.gasIssuancePerSecond,
+                min_gas_excess: base_fee_config.minGasExcess,
+                max_gas_issuance_per_block: base_fee_config.maxGasIssuancePerBlock,
+            },
+            blocks: info
+                .blocks
+                .iter()
+                .map(|block| {
+                    Ok(ProposedBlock {
+                        tx_count: Some(u64::from(block.numTransactions)),
+                        time_shift: u64::from(block.timeShift),
+                        signal_count: block.signalSlots.len().try_into()?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    fn from_ontake(meta: ITaikoL1Ontake::BlockMetadataV2) -> Self {
+        let info_hash = keccak256(meta.abi_encode());
+        let base_fee_config = &meta.baseFeeConfig;
+        Self {
+            fork: Fork::Ontake,
+            batch_id: meta.id,
+            proposer: meta.proposer,
+            coinbase: meta.coinbase,
+            proposed_at: meta.proposedAt,
+            info_hash,
+            txs_hash: meta.blobHash,
+            blob_hashes: if meta.blobUsed {
+                vec![meta.blobHash]
+            } else {
+                Vec::new()
+            },
+            extra_data: meta.extraData,
+            proposed_in: meta.proposedIn,
+            blob_created_in: meta.proposedIn,
+            blob_byte_offset: meta.blobTxListOffset,
+            blob_byte_size: meta.blobTxListLength,
+            gas_limit: meta.gasLimit,
+            last_block_id: meta.id,
+            last_block_timestamp: meta.timestamp,
+            anchor_block_id: meta.anchorBlockId,
+            anchor_block_hash: meta.anchorBlockHash,
+            base_fee_config: BaseFeeConfig {
+                adjustment_quotient: base_fee_config.adjustmentQuotient,
+                sharing_pctg: base_fee_config.sharingPctg,
+                gas_issuance_per_second: base_fee_config.gasIssuancePerSecond,
+                min_gas_excess: base_fee_config.minGasExcess,
+                max_gas_issuance_per_block: base_fee_config.maxGasIssuancePerBlock,
+            },
+            blocks: vec![ProposedBlock {
+                tx_count: None,
+                time_shift: 0,
+                signal_count: 0,
+            }],
+        }
+    }
+
+    /// Id of the first L2 block of the batch
+    pub fn first_block_id(&self) -> Result<u64, Error> {
+        (self.last_block_id + 1)
+            .checked_sub(u64::try_from(self.blocks.len())?)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Batch {} has more blocks than its last block id",
+                    self.batch_id
+                )
+            })
+    }
+}
+
+/// Transition proved for a single batch
+pub struct BatchTransition {
+    pub batch_id: u64,
+    pub parent_hash: B256,
+    pub block_hash: B256,
+    pub state_root: B256,
+    /// Prover named by the event, only Ontake proofs carry it
+    pub prover: Option<Address>,
+}
+
+/// Proof event of any fork
+pub struct ProvedBatches {
+    /// Verifier address on Pacaya. Ontake proofs name their tier instead,
+    /// e.g. `tier:200`.
+    pub verifier: String,
+    pub transitions: Vec<BatchTransition>,
+}
+
+/// Verification event of any fork, which verifies every batch up to
+/// `batch_id`
+pub struct VerifiedBatch {
+    pub batch_id: u64,
+    pub block_hash: B256,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Bytes, U256};
+
+    use super::*;
+    use crate::taiko_inbox_binding::LibSharedData;
+
+    fn log_of(event: &impl SolEvent) -> Log {
+        Log {
+            inner: alloy::primitives::Log {
+                address: Address::ZERO,
+                data: event.encode_log_data(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn batch_info(
+        blocks: Vec<ITaikoInbox::BlockParams>,
+        last_block_id: u64,
+    ) -> ITaikoInbox::BatchInfo {
+        ITaikoInbox::BatchInfo {
+            txsHash: B256::ZERO,
+            blocks,
+            blobHashes: Vec::new(),
+            extraData: B256::ZERO,
+            coinbase: Address::ZERO,
+            proposedIn: 0,
+            blobCreatedIn: 0,
+            blobByteOffset: 0,
+            blobByteSize: 0,
+            gasLimit: 0,
+            lastBlockId: last_block_id,
+            lastBlockTimestamp: 0,
+            anchorBlockId: 0,
+            anchorBlockHash: B256::ZERO,
+            baseFeeConfig: LibSharedData::BaseFeeConfig {
+                adjustmentQuotient: 0,
+                sharingPctg: 0,
+                gasIssuancePerSecond: 0,
+                minGasExcess: 0,
+                maxGasIssuancePerBlock: 0,
+            },
+        }
+    }
+
+    fn ontake_meta(id: u64, blob_hash: B256) -> ITaikoL1Ontake::BlockMetadataV2 {
+        ITaikoL1Ontake::BlockMetadataV2 {
+            anchorBlockHash: B256::ZERO,
+            difficulty: B256::ZERO,
+            blobHash: blob_hash,
+            extraData: B256::ZERO,
+            coinbase: Address::ZERO,
+            id,
+            gasLimit: 0,
+            timestamp: 0,
+            anchorBlockId: 0,
+            minTier: 0,
+            blobUsed: true,
+            parentMetaHash: B256::ZERO,
+            proposer: Address::ZERO,
+            livenessBond: Default::default(),
+            proposedAt: 0,
+            proposedIn: 0,
+            blobTxListOffset: 0,
+            blobTxListLength: 0,
+            blobIndex: 0,
+            baseFeeConfig: ITaikoL1Ontake::BaseFeeConfig {
+                adjustmentQuotient: 0,
+                sharingPctg: 0,
+                gasIssuancePerSecond: 0,
+                minGasExcess: 0,
+                maxGasIssuancePerBlock: 0,
+            },
+        }
+    }
+
+    fn config(pacaya_fork_height: i64, shasta_fork_height: i64) -> ProtocolConfig {
+        ProtocolConfig {
+            proving_window: 7200,
+            cooldown_window: 7200,
+            liveness_bond_base: "0".to_string(),
+            liveness_bond_per_block: "0".to_string(),
+            max_blocks_per_batch: 768,
+            ontake_fork_height: 0,
+            pacaya_fork_height,
+            shasta_fork_height,
+            unzen_fork_height: 0,
+        }
+    }
+
+    #[test]
+    fn decodes_pacaya_batch_proposed() -> Result<(), Error> {
+        let proposer = Address::repeat_byte(1);
+        let log = log_of(&ITaikoInbox::BatchProposed {
+            info: batch_info(
+                vec![
+                    ITaikoInbox::BlockParams {
+                        numTransactions: 3,
+                        timeShift: 0,
+                        signalSlots: Vec::new(),
+                    },
+                    ITaikoInbox::BlockParams {
+                        numTransactions: 5,
+                        timeShift: 2,
+                        signalSlots: vec![B256::ZERO],
+                    },
+                ],
+                101,
+            ),
+            meta: ITaikoInbox::BatchMetadata {
+                infoHash: B256::ZERO,
+                proposer,
+                batchId: 7,
+                proposedAt: 0,
+            },
+            txList: Bytes::new(),
+        });
+
+        let fork = fork_of_log(&log, Fork::proposed_signature);
+        assert_eq!(fork, Some(Fork::Pacaya));
+        assert_eq!(fork_of_log(&log, Fork::proved_signature), None);
+        let batch = Fork::Pacaya.decode_proposed(&log)?;
+        assert_eq!(batch.fork, Fork::Pacaya);
+        assert_eq!(batch.batch_id, 7);
+        assert_eq!(batch.proposer, proposer);
+        assert_eq!(batch.first_block_id()?, 100);
+        assert_eq!(batch.blocks.len(), 2);
+        assert_eq!(batch.blocks[1].tx_count, Some(5));
+        assert_eq!(batch.blocks[1].time_shift, 2);
+        assert_eq!(batch.blocks[1].signal_count, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_ontake_block_proposed_as_single_block_batch() -> Result<(), Error> {
+        let blob_hash = B256::repeat_byte(2);
+        let log = log_of(&ITaikoL1Ontake::BlockProposedV2 {
+            blockId: U256::from(42),
+            meta: ontake_meta(42, blob_hash),
+        });
+
+        assert_eq!(
+            fork_of_log(&log, Fork::proposed_signature),
+            Some(Fork::Ontake)
+        );
+        let batch = Fork::Ontake.decode_proposed(&log)?;
+        assert_eq!(batch.fork, Fork::Ontake);
+        assert_eq!(batch.batch_id, 42);
+        assert_eq!(batch.first_block_id()?, 42);
+        assert_eq!(batch.blob_hashes, vec![blob_hash]);
+        assert_eq!(batch.blocks.len(), 1);
+        assert_eq!(batch.blocks[0].tx_count, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_proofs_of_both_forks() -> Result<(), Error> {
+        let verifier = Address::repeat_byte(3);
+        let pacaya = log_of(&ITaikoInbox::BatchesProved {
+            verifier,
+            batchIds: vec![7, 8],
+            transitions: vec![
+                ITaikoInbox::Transition {
+                    parentHash: B256::ZERO,
+                    blockHash: B256::ZERO,
+                    stateRoot: B256::ZERO,
+                },
+                ITaikoInbox::Transition {
+                    parentHash: B256::ZERO,
+                    blockHash: B256::repeat_byte(4),
+                    stateRoot: B256::ZERO,
+                },
+            ],
+        });
+        assert_eq!(
+            fork_of_log(&pacaya, Fork::proved_signature),
+            Some(Fork::Pacaya)
+        );
+        let proved = Fork::Pacaya.decode_proved(&pacaya)?;
+        assert_eq!(proved.verifier, verifier.to_string());
+        assert_eq!(proved.transitions.len(), 2);
+        assert_eq!(proved.transitions[1].batch_id, 8);
+        assert_eq!(proved.transitions[1].block_hash, B256::repeat_byte(4));
+        assert!(proved.transitions[1].prover.is_none());
+
+        let prover = Address::repeat_byte(5);
+        let ontake = log_of(&ITaikoL1Ontake::TransitionProvedV2 {
+            blockId: U256::from(42),
+            tran: ITaikoL1Ontake::Transition {
+                parentHash: B256::ZERO,
+                blockHash: B256::ZERO,
+                stateRoot: B256::ZERO,
+                graffiti: B256::ZERO,
+            },
+            prover,
+            validityBond: Default::default(),
+            tier: 200,
+            proposedIn: 0,
+        });
+        assert_eq!(
+            fork_of_log(&ontake, Fork::proved_signature),
+            Some(Fork::Ontake)
+        );
+        let proved = Fork::Ontake.decode_proved(&ontake)?;
+        assert_eq!(proved.verifier, "tier:200");
+        assert_eq!(proved.transitions.len(), 1);
+        assert_eq!(proved.transitions[0].batch_id, 42);
+        assert_eq!(proved.transitions[0].prover, Some(prover));
+
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_verifications_of_both_forks() -> Result<(), Error> {
+        let block_hash = B256::repeat_byte(6);
+        let pacaya = log_of(&ITaikoInbox::BatchesVerified {
+            batchId: 7,
+            blockHash: block_hash,
+        });
+        let verified = Fork::Pacaya.decode_verified(&pacaya)?;
+        assert_eq!(verified.batch_id, 7);
+        assert_eq!(verified.block_hash, block_hash);
+
+        let ontake = log_of(&ITaikoL1Ontake::BlockVerifiedV2 {
+            blockId: U256::from(42),
+            prover: Address::ZERO,
+            blockHash: block_hash,
+            tier: 200,
+        });
+        assert_eq!(
+            fork_of_log(&ontake, Fork::verified_signature),
+            Some(Fork::Ontake)
+        );
+        let verified = Fork::Ontake.decode_verified(&ontake)?;
+        assert_eq!(verified.batch_id, 42);
+        assert_eq!(verified.block_hash, block_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn shasta_events_are_not_decoded() {
+        let log = log_of(&ITaikoInbox::BatchesVerified {
+            batchId: 7,
+            blockHash: B256::ZERO,
+        });
+        assert!(Fork::Shasta.decode_verified(&log).is_err());
+        assert!(!Fork::DECODABLE.contains(&Fork::Shasta));
+    }
+
+    #[test]
+    fn selects_fork_by_block_height() {
+        let config = config(100, 0);
+        assert_eq!(Fork::of_block(99, &config), Fork::Ontake);
+        assert_eq!(Fork::of_block(100, &config), Fork::Pacaya);
+        assert_eq!(Fork::of_block(u64::MAX, &config), Fork::Pacaya);
+
+        let config = self::config(100, 200);
+        assert_eq!(Fork::of_block(199, &config), Fork::Pacaya);
+        assert_eq!(Fork::of_block(200, &config), Fork::Shasta);
+    }
+}
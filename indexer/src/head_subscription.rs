@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+
+use alloy::{
+    primitives::Address,
+    providers::{DynProvider, Provider, ProviderBuilder, WsConnect},
+    pubsub::Subscription,
+    rpc::types::{Filter, Header, Log},
+};
+use anyhow::Error;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+/// Inbox logs buffered between two reads, beyond which some are dropped and
+/// ranges fall back to eth_getLogs
+const LOG_CHANNEL_SIZE: usize = 4096;
+
+/// New L1 heads and inbox logs pushed over WebSocket. Heads wake the indexing
+/// loop as soon as a block arrives, logs are kept so that a range they fully
+/// cover is indexed without eth_getLogs.
+pub struct HeadSubscription {
+    // keeps the WebSocket connection alive for the subscriptions
+    _provider: DynProvider,
+    heads: Subscription<Header>,
+    logs: Subscription<Log>,
+    pushed_logs: PushedLogs,
+}
+
+impl HeadSubscription {
+    pub async fn connect(url: &str, taiko_inbox: Address) -> Result<Self, Error> {
+        let provider = ProviderBuilder::new()
+            .connect_ws(WsConnect::new(url))
+            .await?
+            .erased();
+        let heads = provider.subscribe_blocks().await?;
+        let logs = provider
+            .subscribe_logs(&Filter::new().address(taiko_inbox))
+            .channel_size(LOG_CHANNEL_SIZE)
+            .await?;
+        Ok(Self {
+            _provider: provider,
+            heads,
+            logs,
+            pushed_logs: PushedLogs::default(),
+        })
+    }
+
+    /// Waits for the next head and returns its L1 block number, keeping the
+    /// inbox logs received meanwhile. Fails once either subscription is closed.
+    pub async fn next_block(&mut self) -> Result<u64, Error> {
+        loop {
+            tokio::select! {
+                head = self.heads.recv() => match head {
+                    Ok(head) => {
+                        self.pushed_logs.on_head(head.number);
+                        return Ok(head.number);
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::debug!("Skipped {skipped} new head notifications");
+                    }
+                    Err(RecvError::Closed) => {
+                        return Err(anyhow::anyhow!("New heads subscription closed"));
+                    }
+                },
+                log = self.logs.recv() => match log {
+                    Ok(log) => self.pushed_logs.on_log(log),
+                    Err(RecvError::Lagged(skipped)) => self.pushed_logs.on_skipped(skipped),
+                    Err(RecvError::Closed) => {
+                        return Err(anyhow::anyhow!("Inbox logs subscription closed"));
+                    }
+                },
+            }
+        }
+    }
+
+    /// Inbox logs from `from_block` to `to_block` in chain order, None unless
+    /// every log of the range was received
+    pub fn get_logs(&mut self, from_block: u64, to_block: u64) -> Result<Option<Vec<Log>>, Error> {
+        self.receive_pending()?;
+        Ok(self.pushed_logs.get(from_block, to_block))
+    }
+
+    /// Forgets the logs up to `l1_block`, once they are indexed
+    pub fn prune_logs(&mut self, l1_block: u64) {
+        self.pushed_logs.prune(l1_block);
+    }
+
+    /// Forgets every log, after a rollback the range is fetched again
+    pub fn clear_logs(&mut self) {
+        self.pushed_logs.clear();
+    }
+
+    /// Takes the heads and logs received since the last read
+    fn receive_pending(&mut self) -> Result<(), Error> {
+        loop {
+            match self.heads.try_recv() {
+                Ok(head) => self.pushed_logs.on_head(head.number),
+                Err(TryRecvError::Lagged(_)) => {}
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => {
+                    return Err(anyhow::anyhow!("New heads subscription closed"));
+                }
+            }
+        }
+        loop {
+            match self.logs.try_recv() {
+                Ok(log) => self.pushed_logs.on_log(log),
+                Err(TryRecvError::Lagged(skipped)) => self.pushed_logs.on_skipped(skipped),
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Closed) => {
+                    return Err(anyhow::anyhow!("Inbox logs subscription closed"));
+                }
+            }
+        }
+    }
+}
+
+/// Pushed inbox logs by L1 block
+#[derive(Default)]
+struct PushedLogs {
+    /// Latest head received
+    head: u64,
+    /// First L1 block from which every log was received, None until a head
+    /// arrives after subscribing or after logs were dropped
+    complete_from: Option<u64>,
+    logs: BTreeMap<u64, Vec<Log>>,
+}
+
+impl PushedLogs {
+    fn on_head(&mut self, block_number: u64) {
+        self.head = self.head.max(block_number);
+        if self.complete_from.is_none() {
+            self.complete_from = Some(block_number + 1);
+        }
+    }
+
+    fn on_log(&mut self, log: Log) {
+        let Some(block_number) = log.block_number else {
+            return;
+        };
+        let logs = self.logs.entry(block_number).or_default();
+        // a reorg removes the logs of the replaced block
+        logs.retain(|pushed| {
+            pushed.block_hash != log.block_hash || pushed.log_index != log.log_index
+        });
+        if !log.removed {
+            logs.push(log);
+            logs.sort_by_key(|log| log.log_index);
+        }
+    }
+
+    fn on_skipped(&mut self, skipped: u64) {
+        tracing::debug!("Skipped {skipped} inbox log notifications");
+        self.clear();
+    }
+
+    fn get(&self, from_block: u64, to_block: u64) -> Option<Vec<Log>> {
+        // the logs of a block arrive with its head, those of the latest head
+        // may still be on their way
+        if self.complete_from.is_none_or(|block| block > from_block) || self.head <= to_block {
+            return None;
+        }
+        Some(
+            self.logs
+                .range(from_block..=to_block)
+                .flat_map(|(_, logs)| logs.iter().cloned())
+                .collect(),
+        )
+    }
+
+    fn prune(&mut self, l1_block: u64) {
+        self.logs = self.logs.split_off(&(l1_block + 1));
+    }
+
+    fn clear(&mut self) {
+        self.logs.clear();
+        self.complete_from = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::*;
+
+    fn log(block_number: u64, block_hash: u8, log_index: u64) -> Log {
+        Log {
+            block_number: Some(block_number),
+            block_hash: Some(B256::repeat_byte(block_hash)),
+            log_index: Some(log_index),
+            ..Default::default()
+        }
+    }
+
+    fn positions(logs: Option<Vec<Log>>) -> Option<Vec<(u64, u64)>> {
+        logs.map(|logs| {
+            logs.iter()
+                .map(|log| {
+                    (
+                        log.block_number.unwrap_or_default(),
+                        log.log_index.unwrap_or_default(),
+                    )
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn covers_blocks_after_the_first_head() {
+        let mut pushed = PushedLogs::default();
+        assert_eq!(pushed.get(10, 10), None);
+
+        pushed.on_head(10);
+        pushed.on_log(log(11, 1, 3));
+        pushed.on_log(log(11, 1, 1));
+        // the logs of block 11 may not all be there before its head
+        assert_eq!(pushed.get(11, 11), None);
+
+        pushed.on_head(12);
+        assert_eq!(pushed.get(10, 11), None);
+        assert_eq!(positions(pushed.get(11, 11)), Some(vec![(11, 1), (11, 3)]));
+
+        pushed.prune(11);
+        assert_eq!(positions(pushed.get(11, 11)), Some(vec![]));
+    }
+
+    #[test]
+    fn drops_removed_logs() {
+        let mut pushed = PushedLogs::default();
+        pushed.on_head(10);
+        pushed.on_log(log(11, 1, 0));
+        pushed.on_log(Log {
+            removed: true,
+            ..log(11, 1, 0)
+        });
+        pushed.on_log(log(11, 2, 4));
+        pushed.on_head(12);
+
+        assert_eq!(positions(pushed.get(11, 11)), Some(vec![(11, 4)]));
+    }
+
+    #[test]
+    fn skipped_logs_restart_the_coverage() {
+        let mut pushed = PushedLogs::default();
+        pushed.on_head(10);
+        pushed.on_log(log(11, 1, 0));
+        pushed.on_skipped(5);
+        pushed.on_head(12);
+        pushed.on_head(14);
+
+        assert_eq!(pushed.get(11, 13), None);
+        assert_eq!(positions(pushed.get(13, 13)), Some(vec![]));
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+/// Number of L1 blocks indexed per range, adjusted to the provider limits
+pub struct IndexingStep {
+    current: u64,
+    min: u64,
+    max: u64,
+}
+
+impl IndexingStep {
+    /// Ranges indexed faster than this with fewer logs than `SMALL_RANGE_LOGS` grow the step
+    const FAST_RANGE: Duration = Duration::from_secs(2);
+    const SMALL_RANGE_LOGS: usize = 1000;
+
+    pub fn new(initial: u64, min: u64, max: u64) -> Self {
+        Self {
+            current: initial.clamp(min, max),
+            min,
+            max,
+        }
+    }
+
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    /// Step with the same bounds starting at the maximum, for scans that only
+    /// fetch a few logs per range
+    pub fn widest(&self) -> Self {
+        Self {
+            current: self.max,
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    /// Doubles the step after a small and fast range
+    pub fn on_success(&mut self, elapsed: Duration, log_count: usize) {
+        if elapsed < Self::FAST_RANGE && log_count < Self::SMALL_RANGE_LOGS {
+            let next = self.current.saturating_mul(2).min(self.max);
+            if next != self.current {
+                tracing::debug!("Indexing step increased to {}", next);
+                self.current = next;
+            }
+        }
+    }
+
+    /// Halves the step after the provider rejected the range, returns false
+    /// if the step is already at its minimum
+    pub fn shrink(&mut self) -> bool {
+        let next = (self.current / 2).max(self.min);
+        if next == self.current {
+            return false;
+        }
+        self.current = next;
+        true
+    }
+}
@@ -1,13 +1,23 @@
-use anyhow::Error;
+use std::process::ExitCode;
+
 use batch_indexer::BatchIndexer;
 use config::Config;
 mod batch_indexer;
 mod config;
 mod db;
+mod error;
+mod fee_split;
+mod fork;
+mod head_subscription;
+mod indexing_step;
+mod migrations;
+mod range_cache;
+mod rpc_failover;
 mod taiko_inbox_binding;
+mod verifier_registry;
 
 #[tokio::main]
-async fn main() -> Result<(), Error> {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env()) // reads RUST_LOG
         .init();
@@ -15,8 +25,15 @@ async fn main() -> Result<(), Error> {
     tracing::info!("App started");
 
     let config = Config::new();
-    let mut batch_tracker = BatchIndexer::new(config).await?;
-    batch_tracker.run_indexing_loop().await;
+    let mut batch_tracker = match BatchIndexer::new(config).await {
+        Ok(batch_tracker) => batch_tracker,
+        Err(e) => {
+            tracing::error!("Failed to start indexer: {}: {e}", e.kind());
+            return ExitCode::from(e.exit_code());
+        }
+    };
+    let e = batch_tracker.run_indexing_loop().await;
+    tracing::error!("Indexer stopped: {}: {e}", e.kind());
 
-    Ok(())
+    ExitCode::from(e.exit_code())
 }
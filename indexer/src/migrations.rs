@@ -0,0 +1,479 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Error;
+use batch_tracker_common::SCHEMA_VERSION;
+use sqlx::AnyPool;
+
+use crate::db::Backend;
+
+/// Chain data a migration needs for rows indexed before it ran, e.g. a new
+/// column that can't be derived in SQL. The indexer fills it batch by batch
+/// before it resumes indexing.
+#[derive(Clone, Copy, Debug)]
+pub enum Backfill {
+    /// Reads the L1 block every batch was proposed in from its proposal
+    /// receipt
+    ProposeL1Block,
+    /// Marks batches verified by BatchesVerified events that were emitted
+    /// before the upgrade
+    Verification,
+    /// Reads the effective proof of every proved batch from its proof
+    /// transaction, with its prover from the transition stored by the inbox
+    BatchProofs,
+    /// Reads the L2 blocks and the base fee sharing percentage of every batch
+    /// from its BatchProposed event, with their gas used and base fee from
+    /// the L2 RPC, and recomputes the L2 revenue of proved batches from them
+    L2Blocks,
+    /// Reads the cost components of the proposal and proof transactions of
+    /// every batch from their receipts
+    TxCosts,
+    /// Splits the fee of proposal transactions between the batches they
+    /// propose, which were each charged the full fee
+    ProposeFeeSplit,
+    /// Splits the fee of proof transactions between all the batches they
+    /// prove, which were charged an even share of each BatchesProved event
+    ProveFeeSplit,
+    /// Reads the timestamp of the L1 block every batch was proposed in
+    ProposeL1Timestamp,
+    /// Reads the info and meta fields of every batch from its BatchProposed
+    /// event
+    BatchInfo,
+}
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+    /// Backend the SQL runs on, None for both. The others only record the
+    /// version.
+    pub backend: Option<Backend>,
+    pub backfill: Option<Backfill>,
+}
+
+/// Schema migrations in order. Their SQL has to run on both SQLite and
+/// PostgreSQL: BIGINT for integers and for flags stored as 0 or 1, TEXT
+/// and `$N` placeholders.
+/// Migration 1 is the schema of releases before migrations, created only if
+/// missing, so that their databases are upgraded by every later migration.
+/// Its INTEGER columns are 32 bit on PostgreSQL until migration 20.
+/// Applied migrations are never edited, changes go in a new one.
+/// Views are defined once in VIEWS over the latest schema and created again
+/// with the last migration, so a view change also needs a new migration.
+/// The GraphQL server only serves the recorded version that matches the
+/// shared SCHEMA_VERSION, the build fails unless it is the last version here.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: include_str!("../migrations/0001_initial_schema.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 2,
+        description: "reorg detection",
+        sql: include_str!("../migrations/0002_reorg_detection.sql"),
+        backend: None,
+        backfill: Some(Backfill::ProposeL1Block),
+    },
+    Migration {
+        version: 3,
+        description: "batch verification",
+        sql: include_str!("../migrations/0003_verification.sql"),
+        backend: None,
+        backfill: Some(Backfill::Verification),
+    },
+    Migration {
+        version: 4,
+        description: "proof conflicts",
+        sql: include_str!("../migrations/0004_proof_conflict.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 5,
+        description: "bond ledger",
+        sql: include_str!("../migrations/0005_bond_ledger.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 6,
+        description: "batch proofs",
+        sql: include_str!("../migrations/0006_batch_proof.sql"),
+        backend: None,
+        backfill: Some(Backfill::BatchProofs),
+    },
+    Migration {
+        version: 7,
+        description: "pending proofs",
+        sql: include_str!("../migrations/0007_pending_proof.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 8,
+        description: "L2 fee revenue from L2 blocks",
+        sql: include_str!("../migrations/0008_l2_fee_revenue.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 9,
+        description: "L2 blocks",
+        sql: include_str!("../migrations/0009_l2_block.sql"),
+        backend: None,
+        backfill: Some(Backfill::L2Blocks),
+    },
+    Migration {
+        version: 10,
+        description: "transaction costs",
+        sql: include_str!("../migrations/0010_tx_cost.sql"),
+        backend: None,
+        backfill: Some(Backfill::TxCosts),
+    },
+    Migration {
+        version: 11,
+        description: "propose fee split",
+        sql: include_str!("../migrations/0011_propose_fee_split.sql"),
+        backend: None,
+        backfill: Some(Backfill::ProposeFeeSplit),
+    },
+    Migration {
+        version: 12,
+        description: "prove fee split",
+        sql: include_str!("../migrations/0012_prove_fee_split.sql"),
+        backend: None,
+        backfill: Some(Backfill::ProveFeeSplit),
+    },
+    Migration {
+        version: 13,
+        description: "prover attribution",
+        sql: include_str!("../migrations/0013_prover_attribution.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 14,
+        description: "verifier registry",
+        sql: include_str!("../migrations/0014_verifier.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 15,
+        description: "L1 timestamps",
+        sql: include_str!("../migrations/0015_l1_timestamps.sql"),
+        backend: None,
+        backfill: Some(Backfill::ProposeL1Timestamp),
+    },
+    Migration {
+        version: 16,
+        description: "batch info",
+        sql: include_str!("../migrations/0016_batch_info.sql"),
+        backend: None,
+        backfill: Some(Backfill::BatchInfo),
+    },
+    Migration {
+        version: 17,
+        description: "protocol config history",
+        sql: include_str!("../migrations/0017_protocol_config.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 18,
+        description: "protocol forks",
+        sql: include_str!("../migrations/0018_fork.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 19,
+        description: "flag columns",
+        sql: include_str!("../migrations/0019_flag_columns.sql"),
+        backend: None,
+        backfill: None,
+    },
+    Migration {
+        version: 20,
+        description: "BIGINT columns of the initial schema",
+        sql: include_str!("../migrations/0020_bigint_columns.sql"),
+        backend: Some(Backend::Postgres),
+        backfill: None,
+    },
+];
+
+// the GraphQL server refuses a database at any other version
+const _: () = assert!(MIGRATIONS[MIGRATIONS.len() - 1].version == SCHEMA_VERSION);
+
+/// Views over the tables, created after the last migration
+const VIEWS: &str = include_str!("../migrations/views.sql");
+const DROP_VIEWS: &str = "DROP VIEW IF EXISTS batch_view; DROP VIEW IF EXISTS batch_proof_view;";
+
+pub fn get_backfill(version: i64) -> Option<Backfill> {
+    MIGRATIONS
+        .iter()
+        .find(|migration| migration.version == version)
+        .and_then(|migration| migration.backfill)
+}
+
+/// Applies every migration newer than the recorded schema version, each in its
+/// own transaction together with its version record
+pub async fn run(pool: &AnyPool, backend: Backend) -> Result<(), Error> {
+    sqlx::raw_sql(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migration (
+            version      BIGINT PRIMARY KEY,
+            description  TEXT NOT NULL,
+            applied_at   BIGINT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS backfill (
+            version        BIGINT PRIMARY KEY,
+            next_batch_id  BIGINT NOT NULL,
+            last_batch_id  BIGINT NOT NULL
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let current_version: i64 =
+        sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_migration")
+            .fetch_one(pool)
+            .await?;
+    let latest_version = MIGRATIONS.last().map_or(0, |migration| migration.version);
+    if current_version > latest_version {
+        return Err(anyhow::anyhow!(
+            "Database schema version {current_version} is newer than the latest known version {latest_version}"
+        ));
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current_version)
+    {
+        tracing::info!(
+            "Applying migration {}: {}",
+            migration.version,
+            migration.description
+        );
+        let mut tx = pool.begin().await?;
+        sqlx::raw_sql(DROP_VIEWS).execute(&mut *tx).await?;
+        if migration.backend.is_none_or(|only| only == backend) {
+            sqlx::raw_sql(migration.sql).execute(&mut *tx).await?;
+        }
+        if migration.version == latest_version {
+            sqlx::raw_sql(VIEWS).execute(&mut *tx).await?;
+        }
+
+        if migration.backfill.is_some() {
+            // only batches that exist now need it, later ones are indexed with the new columns
+            sqlx::query(
+                r#"
+                INSERT INTO backfill (version, next_batch_id, last_batch_id)
+                SELECT CAST($1 AS BIGINT), MIN(batch_id), MAX(batch_id) FROM batch HAVING COUNT(*) > 0
+                "#,
+            )
+            .bind(migration.version)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO schema_migration (version, description, applied_at)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(unix_timestamp()?)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+fn unix_timestamp() -> Result<i64, Error> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_secs()
+        .try_into()?)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use sqlx::any::{AnyPoolOptions, install_default_drivers};
+
+    use super::*;
+
+    async fn memory_pool() -> Result<AnyPool, Error> {
+        install_default_drivers();
+        Ok(AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?)
+    }
+
+    /// URL of a new empty schema in the PostgreSQL database at
+    /// TEST_DATABASE_URL, None when it is not set
+    pub(crate) async fn postgres_url() -> Result<Option<String>, Error> {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            return Ok(None);
+        };
+        install_default_drivers();
+        let schema = format!("test_{}", rand::random::<u64>());
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect(&database_url)
+            .await?;
+        sqlx::raw_sql(&format!("CREATE SCHEMA {schema}"))
+            .execute(&pool)
+            .await?;
+        pool.close().await;
+
+        let separator = if database_url.contains('?') { '&' } else { '?' };
+        Ok(Some(format!(
+            "{database_url}{separator}options=-c%20search_path%3D{schema}"
+        )))
+    }
+
+    async fn schema_version(pool: &AnyPool) -> Result<i64, Error> {
+        Ok(
+            sqlx::query_scalar("SELECT MAX(version) FROM schema_migration")
+                .fetch_one(pool)
+                .await?,
+        )
+    }
+
+    fn latest_version() -> i64 {
+        MIGRATIONS.last().map_or(0, |migration| migration.version)
+    }
+
+    #[test]
+    fn versions_are_consecutive() {
+        for (version, migration) in (1..).zip(MIGRATIONS) {
+            assert_eq!(migration.version, version);
+        }
+    }
+
+    #[tokio::test]
+    async fn migrates_a_fresh_database() -> Result<(), Error> {
+        let pool = memory_pool().await?;
+        run(&pool, Backend::Sqlite).await?;
+        assert_eq!(schema_version(&pool).await?, latest_version());
+        // no batch needs a backfill
+        let backfills: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM backfill")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(backfills, 0);
+
+        // an up to date database is left as is
+        run(&pool, Backend::Sqlite).await?;
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_migration")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(applied, i64::try_from(MIGRATIONS.len())?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upgrades_a_baseline_database() -> Result<(), Error> {
+        upgrade_baseline_database(&memory_pool().await?, Backend::Sqlite).await
+    }
+
+    #[tokio::test]
+    async fn upgrades_a_baseline_postgres_database() -> Result<(), Error> {
+        let Some(database_url) = postgres_url().await? else {
+            return Ok(());
+        };
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect(&database_url)
+            .await?;
+        upgrade_baseline_database(&pool, Backend::Postgres).await?;
+
+        // ids and timestamps past the 32 bit range of the initial schema
+        let batch_id = i64::from(i32::MAX) + 1;
+        sqlx::query("UPDATE batch SET batch_id = $1, proposed_at = $2 WHERE batch_id = 5")
+            .bind(batch_id)
+            .bind(batch_id)
+            .execute(&pool)
+            .await?;
+        sqlx::query("UPDATE status SET indexed_l1_block = $1, proposed_batch_id = $2")
+            .bind(batch_id)
+            .bind(batch_id)
+            .execute(&pool)
+            .await?;
+        let proposed_at: i64 =
+            sqlx::query_scalar("SELECT proposed_at FROM batch_view WHERE batch_id = $1")
+                .bind(batch_id)
+                .fetch_one(&pool)
+                .await?;
+        assert_eq!(proposed_at, batch_id);
+
+        Ok(())
+    }
+
+    async fn upgrade_baseline_database(pool: &AnyPool, backend: Backend) -> Result<(), Error> {
+        // database of a release before migrations
+        sqlx::raw_sql(MIGRATIONS[0].sql).execute(pool).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO batch (
+                batch_id, sender, proposer, coinbase, propose_tx, proposed_at, last_block_id,
+                block_count, propose_fee, l2_fee_earned, prover, prove_tx, prove_fee,
+                is_sent_by_proposer, is_profitable, is_proved_by_proposer
+            )
+            VALUES (5, 'sender', 'proposer', 'coinbase', 'propose', 0, 10, 2, '1', '2',
+                'prover', 'prove', '3', TRUE, FALSE, TRUE)
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        run(pool, backend).await?;
+
+        assert_eq!(schema_version(pool).await?, latest_version());
+        let backfills: Vec<(i64, i64, i64)> = sqlx::query_as(
+            "SELECT version, next_batch_id, last_batch_id FROM backfill ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await?;
+        let expected: Vec<(i64, i64, i64)> = MIGRATIONS
+            .iter()
+            .filter(|migration| migration.backfill.is_some())
+            .map(|migration| (migration.version, 5, 5))
+            .collect();
+        assert_eq!(backfills, expected);
+
+        let (is_sent_by_proposer, fork): (i64, String) =
+            sqlx::query_as("SELECT is_sent_by_proposer, fork FROM batch_view WHERE batch_id = 5")
+                .fetch_one(pool)
+                .await?;
+        assert_eq!(is_sent_by_proposer, 1);
+        assert_eq!(fork, "pacaya");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refuses_a_newer_database() -> Result<(), Error> {
+        let pool = memory_pool().await?;
+        run(&pool, Backend::Sqlite).await?;
+        sqlx::query(
+            "INSERT INTO schema_migration (version, description, applied_at) VALUES ($1, 'future', 0)",
+        )
+        .bind(latest_version() + 1)
+        .execute(&pool)
+        .await?;
+
+        assert!(run(&pool, Backend::Sqlite).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use alloy::{
+    primitives::{B256, TxHash},
+    rpc::types::{Header, TransactionReceipt},
+};
+
+use crate::taiko_inbox_binding::ITaikoInbox::TransitionState;
+
+/// L2 block header with the receipts of its transactions
+#[derive(Clone)]
+pub struct L2Block {
+    pub header: Header,
+    pub receipts: Vec<TransactionReceipt>,
+}
+
+/// Batch id, L1 block and parent hash a transition is read with
+pub type TransitionKey = (u64, u64, B256);
+
+/// Receipts, L1 headers, transitions, L2 blocks and headers fetched for the current
+/// range, so that every one is requested only once per range and none while
+/// its database transaction is open
+#[derive(Default)]
+pub struct RangeCache {
+    receipts: Mutex<HashMap<TxHash, TransactionReceipt>>,
+    headers: Mutex<HashMap<u64, Header>>,
+    transitions: Mutex<HashMap<TransitionKey, Option<TransitionState>>>,
+    l2_blocks: Mutex<HashMap<u64, L2Block>>,
+    l2_headers: Mutex<HashMap<u64, Header>>,
+    /// Set once the range is fetched, transitions and L2 blocks or headers
+    /// missing from the cache are then not fetched anymore
+    sealed: AtomicBool,
+}
+
+impl RangeCache {
+    pub fn seal(&self) {
+        self.sealed.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        self.sealed.store(false, Ordering::Relaxed);
+        self.receipts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.headers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.transitions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.l2_blocks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.l2_headers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    pub fn get_receipt(&self, tx_hash: &TxHash) -> Option<TransactionReceipt> {
+        self.receipts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(tx_hash)
+            .cloned()
+    }
+
+    pub fn has_receipt(&self, tx_hash: &TxHash) -> bool {
+        self.receipts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(tx_hash)
+    }
+
+    pub fn insert_receipt(&self, receipt: TransactionReceipt) {
+        self.receipts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(receipt.transaction_hash, receipt);
+    }
+
+    pub fn get_header(&self, block_number: u64) -> Option<Header> {
+        self.headers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&block_number)
+            .cloned()
+    }
+
+    pub fn has_header(&self, block_number: u64) -> bool {
+        self.headers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(&block_number)
+    }
+
+    pub fn insert_header(&self, header: Header) {
+        self.headers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(header.number, header);
+    }
+
+    pub fn get_transition(&self, key: &TransitionKey) -> Option<Option<TransitionState>> {
+        self.transitions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)
+            .cloned()
+    }
+
+    pub fn insert_transition(&self, key: TransitionKey, transition: Option<TransitionState>) {
+        self.transitions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, transition);
+    }
+
+    pub fn get_l2_block(&self, block_number: u64) -> Option<L2Block> {
+        self.l2_blocks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&block_number)
+            .cloned()
+    }
+
+    pub fn insert_l2_block(&self, block: L2Block) {
+        self.l2_blocks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(block.header.number, block);
+    }
+
+    /// Header of an L2 block, also found in the L2 blocks fetched with their
+    /// receipts
+    pub fn get_l2_header(&self, block_number: u64) -> Option<Header> {
+        if let Some(block) = self.get_l2_block(block_number) {
+            return Some(block.header);
+        }
+        self.l2_headers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&block_number)
+            .cloned()
+    }
+
+    pub fn insert_l2_header(&self, header: Header) {
+        self.l2_headers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(header.number, header);
+    }
+}
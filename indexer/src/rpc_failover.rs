@@ -0,0 +1,295 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    providers::{DynProvider, Provider, ProviderBuilder},
+    rpc::{
+        client::RpcClient,
+        json_rpc::{ErrorPayload, RequestPacket, ResponsePacket},
+    },
+    transports::{
+        TransportError, TransportErrorKind, TransportFut, TransportResult,
+        http::{Http, reqwest::Url},
+    },
+};
+use anyhow::Error;
+use tower::Service;
+
+/// Consecutive failures after which an endpoint is put on cooldown
+const UNHEALTHY_AFTER_FAILURES: u32 = 3;
+/// How long an unhealthy endpoint is only used as a last resort
+const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+/// Weight of the newest sample in the latency and error rate averages, in 1/8
+const EWMA_WEIGHT: u64 = 1;
+
+/// RPC endpoint parsed from `url` or `url|priority`, lower priority is preferred
+#[derive(Debug, Clone)]
+pub struct RpcEndpoint {
+    pub url: Url,
+    pub priority: u32,
+}
+
+/// Parses a comma separated list of endpoints. Endpoints without an explicit
+/// priority get priority 0, so they are ranked by their score only.
+pub fn parse_endpoints(value: &str) -> Result<Vec<RpcEndpoint>, Error> {
+    let endpoints = value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (url, priority) = match entry.rsplit_once('|') {
+                Some((url, priority)) => (url, priority.trim().parse::<u32>()?),
+                None => (entry, 0),
+            };
+            Ok(RpcEndpoint {
+                url: url.trim().parse()?,
+                priority,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    if endpoints.is_empty() {
+        return Err(anyhow::anyhow!("No RPC endpoints configured"));
+    }
+    Ok(endpoints)
+}
+
+#[derive(Default)]
+struct Health {
+    /// Moving average of the request latency in milliseconds, None until a
+    /// request succeeds
+    latency_ms: Option<u64>,
+    /// Moving average of the error rate in per mille
+    error_rate: u64,
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+}
+
+impl Health {
+    fn is_unhealthy(&self, now: Instant) -> bool {
+        self.unhealthy_until.is_some_and(|until| until > now)
+    }
+
+    /// Latency weighted by the error rate, lower is better, None for an
+    /// endpoint that has not been measured yet
+    fn score(&self) -> Option<u64> {
+        self.latency_ms.map(|latency_ms| {
+            latency_ms.saturating_mul(1000 + self.error_rate.saturating_mul(4)) / 1000
+        })
+    }
+}
+
+fn ewma(average: u64, sample: u64) -> u64 {
+    average
+        .saturating_mul(8 - EWMA_WEIGHT)
+        .saturating_add(sample.saturating_mul(EWMA_WEIGHT))
+        / 8
+}
+
+struct Endpoint {
+    url: Url,
+    priority: u32,
+    transport: Http<alloy::transports::http::Client>,
+    health: Mutex<Health>,
+}
+
+impl Endpoint {
+    fn health(&self) -> std::sync::MutexGuard<'_, Health> {
+        self.health.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn record_success(&self, elapsed: Duration) {
+        let mut health = self.health();
+        let latency_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        // the first sample seeds the average, so that it does not start from 0
+        health.latency_ms = Some(
+            health
+                .latency_ms
+                .map_or(latency_ms, |average| ewma(average, latency_ms)),
+        );
+        health.error_rate = ewma(health.error_rate, 0);
+        if health.unhealthy_until.take().is_some() {
+            tracing::info!("RPC endpoint {} recovered", self.url);
+        }
+        health.consecutive_failures = 0;
+    }
+
+    fn record_failure(&self, reason: &str) {
+        let mut health = self.health();
+        health.error_rate = ewma(health.error_rate, 1000);
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        if health.consecutive_failures >= UNHEALTHY_AFTER_FAILURES {
+            tracing::warn!(
+                "RPC endpoint {} marked unhealthy after {} consecutive failures: {}",
+                self.url,
+                health.consecutive_failures,
+                reason
+            );
+            health.unhealthy_until = Some(Instant::now() + UNHEALTHY_COOLDOWN);
+        } else {
+            tracing::warn!("RPC endpoint {} failed: {}", self.url, reason);
+        }
+    }
+}
+
+/// HTTP transport over several endpoints of the same chain. Requests go to the
+/// best ranked endpoint and fail over to the next one on transport errors and
+/// rate limit responses.
+#[derive(Clone)]
+pub struct FailoverTransport {
+    endpoints: Vec<Arc<Endpoint>>,
+}
+
+impl FailoverTransport {
+    pub fn new(endpoints: Vec<RpcEndpoint>) -> Self {
+        Self {
+            endpoints: endpoints
+                .into_iter()
+                .map(|endpoint| {
+                    Arc::new(Endpoint {
+                        transport: Http::new(endpoint.url.clone()),
+                        url: endpoint.url,
+                        priority: endpoint.priority,
+                        health: Mutex::new(Health::default()),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    pub fn provider(&self) -> DynProvider {
+        ProviderBuilder::new()
+            .connect_client(RpcClient::new(self.clone(), false))
+            .erased()
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// URLs of the endpoints behind this transport, for log messages
+    pub fn urls(&self) -> String {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.url.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Returns up to `count` transports pinned to a single endpoint each, best
+    /// ranked first. They share health with this transport but never fail over.
+    pub fn ranked_endpoints(&self, count: usize) -> Vec<FailoverTransport> {
+        self.ranked()
+            .into_iter()
+            .take(count)
+            .map(|endpoint| FailoverTransport {
+                endpoints: vec![endpoint],
+            })
+            .collect()
+    }
+
+    /// Records a failure on every endpoint behind this transport, used when a
+    /// response was accepted but later found to be wrong
+    pub fn penalize(&self, reason: &str) {
+        for endpoint in &self.endpoints {
+            endpoint.record_failure(reason);
+        }
+    }
+
+    /// Endpoints ordered by health, priority and score, then by their position
+    /// in the list. Unhealthy endpoints are kept at the end so they are still
+    /// tried when everything else fails. Within a priority, endpoints without a
+    /// measured latency come after the measured ones.
+    fn ranked(&self) -> Vec<Arc<Endpoint>> {
+        let now = Instant::now();
+        let mut ranked: Vec<_> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let health = endpoint.health();
+                let score = health.score();
+                (
+                    (
+                        health.is_unhealthy(now),
+                        endpoint.priority,
+                        score.is_none(),
+                        score.unwrap_or_default(),
+                    ),
+                    endpoint.clone(),
+                )
+            })
+            .collect();
+        ranked.sort_by_key(|(rank, _)| *rank);
+        ranked.into_iter().map(|(_, endpoint)| endpoint).collect()
+    }
+
+    async fn dispatch(self, request: RequestPacket) -> TransportResult<ResponsePacket> {
+        let mut last_result = None;
+        for endpoint in self.ranked() {
+            let started = Instant::now();
+            let result = endpoint.transport.clone().call(request.clone()).await;
+            let failure = match &result {
+                Ok(response) => response
+                    .iter_errors()
+                    .find(|error| error.is_retry_err())
+                    .map(error_payload_reason),
+                Err(error) => Some(error.to_string()),
+            };
+            match failure {
+                Some(reason) => endpoint.record_failure(&reason),
+                None => {
+                    endpoint.record_success(started.elapsed());
+                    return result;
+                }
+            }
+            last_result = Some(result);
+        }
+        last_result.unwrap_or_else(|| {
+            Err(TransportErrorKind::custom_str(
+                "No RPC endpoints configured",
+            ))
+        })
+    }
+}
+
+fn error_payload_reason(error: &ErrorPayload) -> String {
+    format!("error code {}: {}", error.code, error.message)
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().dispatch(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_unmeasured_endpoints_after_measured_ones() -> Result<(), Error> {
+        let transport = FailoverTransport::new(parse_endpoints(
+            "http://a.invalid,http://b.invalid,http://c.invalid",
+        )?);
+        transport.endpoints[1].record_success(Duration::from_millis(200));
+        transport.endpoints[2].record_success(Duration::from_millis(100));
+
+        let hosts: Vec<_> = transport
+            .ranked()
+            .iter()
+            .map(|endpoint| endpoint.url.host_str().unwrap_or_default().to_string())
+            .collect();
+        assert_eq!(hosts, ["c.invalid", "b.invalid", "a.invalid"]);
+        Ok(())
+    }
+}
@@ -6,3 +6,71 @@ sol! {
     ITaikoInbox,
     "abi/ITaikoInbox.json"
 }
+
+sol! {
+    /// Proxy the inbox is deployed behind, upgrades can change its config
+    interface IERC1967 {
+        event Upgraded(address indexed implementation);
+    }
+}
+
+sol! {
+    /// Events of TaikoL1 before the Pacaya fork, when every proposal was a
+    /// single block. The inbox proxy emitted them until it was upgraded.
+    #[allow(missing_docs)]
+    interface ITaikoL1Ontake {
+        struct BaseFeeConfig {
+            uint8 adjustmentQuotient;
+            uint8 sharingPctg;
+            uint32 gasIssuancePerSecond;
+            uint64 minGasExcess;
+            uint32 maxGasIssuancePerBlock;
+        }
+
+        struct BlockMetadataV2 {
+            bytes32 anchorBlockHash;
+            bytes32 difficulty;
+            bytes32 blobHash;
+            bytes32 extraData;
+            address coinbase;
+            uint64 id;
+            uint32 gasLimit;
+            uint64 timestamp;
+            uint64 anchorBlockId;
+            uint16 minTier;
+            bool blobUsed;
+            bytes32 parentMetaHash;
+            address proposer;
+            uint96 livenessBond;
+            uint64 proposedAt;
+            uint64 proposedIn;
+            uint32 blobTxListOffset;
+            uint32 blobTxListLength;
+            uint8 blobIndex;
+            BaseFeeConfig baseFeeConfig;
+        }
+
+        struct Transition {
+            bytes32 parentHash;
+            bytes32 blockHash;
+            bytes32 stateRoot;
+            bytes32 graffiti;
+        }
+
+        event BlockProposedV2(uint256 indexed blockId, BlockMetadataV2 meta);
+        event TransitionProvedV2(
+            uint256 indexed blockId,
+            Transition tran,
+            address prover,
+            uint96 validityBond,
+            uint16 tier,
+            uint64 proposedIn
+        );
+        event BlockVerifiedV2(
+            uint256 indexed blockId,
+            address indexed prover,
+            bytes32 blockHash,
+            uint16 tier
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fmt, str::FromStr};
+
+use alloy::primitives::Address;
+use anyhow::{Context, Error};
+
+/// Verifier named by a proof: the verifier contract on Pacaya, the proof tier
+/// on Ontake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verifier {
+    Address(Address),
+    Tier(u16),
+}
+
+impl fmt::Display for Verifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verifier::Address(address) => write!(f, "{address}"),
+            Verifier::Tier(tier) => write!(f, "tier:{tier}"),
+        }
+    }
+}
+
+impl FromStr for Verifier {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("tier:") {
+            Some(tier) => Ok(Verifier::Tier(tier.parse()?)),
+            None => Ok(Verifier::Address(Address::from_str(s)?)),
+        }
+    }
+}
+
+/// Verifier mapped to the proof type it checks, e.g. sgx or sp1
+#[derive(Debug, Clone)]
+pub struct VerifierEntry {
+    pub verifier: Verifier,
+    pub proof_type: String,
+}
+
+/// Parses a comma separated list of `address=proof_type` entries, with
+/// `tier:N=proof_type` for the tiers of Ontake proofs. Proof types are
+/// lowercased so that reports group them regardless of spelling.
+pub fn parse_verifier_registry(value: &str) -> Result<Vec<VerifierEntry>, Error> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (verifier, proof_type) = entry.split_once('=').ok_or_else(|| {
+                anyhow::anyhow!("Verifier entry {entry} is not address=proof_type")
+            })?;
+            let proof_type = proof_type.trim().to_lowercase();
+            if proof_type.is_empty() {
+                return Err(anyhow::anyhow!("Verifier entry {entry} has no proof type"));
+            }
+            Ok(VerifierEntry {
+                verifier: Verifier::from_str(verifier.trim())
+                    .with_context(|| format!("Invalid verifier in entry {entry}"))?,
+                proof_type,
+            })
+        })
+        .collect()
+}